@@ -0,0 +1,85 @@
+//! Compares running the Day 19 part 1 program on the wrist computer (registers updated in place)
+//! against the original implementation, which cloned the registers into a new Vec for every
+//! instruction and used plain usize arithmetic.
+//!
+//! Run with "cargo bench --bench wristcomp".
+
+use advent_of_code_2018::utils::wristcomp::Instruction;
+use advent_of_code_2018::utils::wristcomp::Operation;
+use advent_of_code_2018::utils::wristcomp::Program;
+use advent_of_code_2018::utils::wristcomp::WristComputer;
+
+use std::time::Duration;
+use std::time::Instant;
+
+const RUNS: u32 = 5;
+
+/// Performs the operation the way the original wrist computer did, returning a copy of the
+/// registers with the result written to them.
+fn perform_operation_with_copy(before: &Vec<usize>, instruction: &Instruction) -> Vec<usize> {
+    let values = instruction.get_values();
+    let mut after = before.clone();
+    after[values.2] = match instruction.get_operation() {
+        Operation::AddReg => before[values.0] + before[values.1],
+        Operation::AddImm => before[values.0] + values.1,
+        Operation::MulReg => before[values.0] * before[values.1],
+        Operation::MulImm => before[values.0] * values.1,
+        Operation::BitANDReg => before[values.0] & before[values.1],
+        Operation::BitANDImm => before[values.0] & values.1,
+        Operation::BitORReg => before[values.0] | before[values.1],
+        Operation::BitORImm => before[values.0] | values.1,
+        Operation::SetReg => before[values.0],
+        Operation::SetImm => values.0,
+        Operation::GtImmReg => if values.0 > before[values.1] { 1 } else { 0 },
+        Operation::GtRegImm => if before[values.0] > values.1 { 1 } else { 0 },
+        Operation::GtRegReg => if before[values.0] > before[values.1] { 1 } else { 0 },
+        Operation::EqImmReg => if values.0 == before[values.1] { 1 } else { 0 },
+        Operation::EqRegImm => if before[values.0] == values.1 { 1 } else { 0 },
+        Operation::EqRegReg => if before[values.0] == before[values.1] { 1 } else { 0 },
+    };
+    return after;
+}
+
+/// Executes the program by copying the registers for every instruction, returning register 0.
+fn execute_with_register_copies(program: &[Instruction], ip_reg: usize) -> usize {
+    let mut registers = vec![0; 6];
+    let mut ip_val = 0;
+    while ip_val < program.len() {
+        registers[ip_reg] = ip_val;
+        registers = perform_operation_with_copy(&registers, &program[ip_val]);
+        ip_val = registers[ip_reg] + 1;
+    }
+    return registers[0];
+}
+
+/// Executes the program on the wrist computer, returning register 0.
+fn execute_in_place(program: &[Instruction], ip_reg: usize) -> usize {
+    let mut wrist_comp = WristComputer::new(Some(ip_reg));
+    wrist_comp.execute_program(program);
+    return wrist_comp.get_registers()[0];
+}
+
+/// Gets the fastest time taken out of several runs of the function.
+fn time_fastest_run(f: &dyn Fn() -> usize) -> (usize, Duration) {
+    let mut fastest = Duration::MAX;
+    let mut result = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = f();
+        fastest = fastest.min(start.elapsed());
+    }
+    return (result, fastest);
+}
+
+fn main() {
+    let input = std::fs::read_to_string("./input/2018/day19.txt").unwrap();
+    let program = input.parse::<Program>().unwrap();
+    let ip_reg = program.get_ip_reg().unwrap();
+    let instructions = program.get_instructions();
+    let (copy_result, copy_time) = time_fastest_run(&|| execute_with_register_copies(instructions, ip_reg));
+    let (in_place_result, in_place_time) = time_fastest_run(&|| execute_in_place(instructions, ip_reg));
+    assert_eq!(copy_result, in_place_result);
+    println!("register copies: {:>10.3?}", copy_time);
+    println!("in place:        {:>10.3?}", in_place_time);
+    println!("speedup:         {:>9.2}x", copy_time.as_secs_f64() / in_place_time.as_secs_f64());
+}
@@ -0,0 +1,67 @@
+//! Terminal animation of the puzzles that simulate a map over time.
+//!
+//! Run with "cargo run --release --bin animate -- <day> <input file> [frame delay ms]", for example
+//! "cargo run --release --bin animate -- 13 input/2018/day13.txt 50". Controls are entered one per
+//! line: enter (or "p") to play/pause, "s" to step one tick, "+"/"-" to change speed and "q" to
+//! quit.
+
+use advent_of_code_2018::day_13;
+use advent_of_code_2018::day_15;
+use advent_of_code_2018::day_17;
+use advent_of_code_2018::day_18;
+use advent_of_code_2018::utils::animate::Animator;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+/// Delay between frames used if none is given on the command line.
+const DEFAULT_FRAME_DELAY_MS: u64 = 100;
+
+/// Days that can be animated.
+const DAYS: &str = "13 (mine cart madness), 15 (beverage bandits), 17 (reservoir research), 18 (settlers of the north pole)";
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() != 3 && args.len() != 4 {
+        eprintln!("Usage: {} <day> <input file> [frame delay ms]", args[0]);
+        eprintln!("Days: {}", DAYS);
+        process::exit(2);
+    }
+    let frame_delay_ms = match args.get(3) {
+        Some(arg) => match arg.parse::<u64>() {
+            Ok(delay) => delay,
+            Err(_) => {
+                eprintln!("Invalid frame delay \"{}\"", arg);
+                process::exit(2);
+            }
+        },
+        None => DEFAULT_FRAME_DELAY_MS,
+    };
+    let input = match fs::read_to_string(&args[2]) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("Failed to read \"{}\": {}", args[2], err);
+            process::exit(1);
+        }
+    };
+    let mut animator = Animator::new(Duration::from_millis(frame_delay_ms));
+    let result = match args[1].as_str() {
+        "13" => day_13::animate(&input, &mut animator),
+        "15" => day_15::animate(&input, &mut animator),
+        "17" => day_17::animate(&input, &mut animator),
+        "18" => day_18::animate(&input, &mut animator),
+        day => {
+            eprintln!("Day {} cannot be animated - choose from: {}", day, DAYS);
+            process::exit(2);
+        }
+    };
+    match result {
+        Ok(ticks) => println!("Animation stopped after {} ticks.", ticks),
+        Err(err) => {
+            eprintln!("Failed to draw animation: {}", err);
+            process::exit(1);
+        }
+    }
+}
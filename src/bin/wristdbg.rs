@@ -0,0 +1,272 @@
+//! Interactive debugger for wrist computer programs, such as the Day 19 puzzle input.
+//!
+//! Run with "cargo run --bin wristdbg -- input/2018/day19.txt" and enter "help" for the list of
+//! commands.
+
+use advent_of_code_2018::utils::wristcomp::disassemble_line;
+use advent_of_code_2018::utils::wristcomp::DEFAULT_REGISTER_COUNT;
+use advent_of_code_2018::utils::wristcomp::Program;
+use advent_of_code_2018::utils::wristcomp::StopReason;
+use advent_of_code_2018::utils::wristcomp::WatchCondition;
+use advent_of_code_2018::utils::wristcomp::WristComputer;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::process;
+
+/// Number of instructions shown either side of the instruction pointer when disassembling.
+const DISASM_CONTEXT: usize = 5;
+
+/// Maximum number of executed instructions that can be undone.
+const HISTORY_LIMIT: usize = 100000;
+
+const HELP: &str = "\
+Commands:
+  step [n]            execute the next n instructions (default 1)
+  back [n]            undo the last n executed instructions (default 1)
+  continue            run until the program halts or stops at a breakpoint or watchpoint
+  break <addr>        set a breakpoint on an instruction address
+  unbreak <addr>      remove the breakpoint on an instruction address
+  watch <reg>         stop whenever an instruction changes the value of a register
+  unwatch <reg>       remove the watchpoint on a register
+  set <reg> <val>     set the value held in a register (moving the ip if it is bound to it)
+  print               print the instruction pointer, step count and registers
+  disassemble         disassemble the instructions around the instruction pointer
+  reset               restart the program with all registers cleared
+  quit                exit the debugger";
+
+/// Debugger session holding the loaded program and the wrist computer executing it.
+struct Debugger {
+    program: Program,
+    wrist_comp: WristComputer,
+}
+
+impl Debugger {
+    /// Creates a new debugger session, returning an error if the program binds the instruction
+    /// pointer to a register the wrist computer does not have.
+    fn new(program: Program) -> Result<Self, String> {
+        if let Some(ip_reg) = program.get_ip_reg() {
+            if ip_reg >= DEFAULT_REGISTER_COUNT {
+                return Err(format!("instruction pointer register {} out of range (wrist computer has {} registers)",
+                    ip_reg, DEFAULT_REGISTER_COUNT));
+            }
+        }
+        let wrist_comp = Debugger::new_wrist_computer(&program);
+        return Ok(Self {
+            program: program,
+            wrist_comp: wrist_comp,
+        });
+    }
+
+    fn new_wrist_computer(program: &Program) -> WristComputer {
+        let mut wrist_comp = WristComputer::new(program.get_ip_reg());
+        wrist_comp.enable_history(Some(HISTORY_LIMIT));
+        return wrist_comp;
+    }
+
+    /// Executes the given command line. Returns false if the debugger should exit.
+    fn execute_command(&mut self, line: &str) -> Result<bool, String> {
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        if tokens.is_empty() {
+            return Ok(true);
+        }
+        match (tokens[0], &tokens[1..]) {
+            ("step", args) | ("s", args) => self.step(parse_count(args)?),
+            ("back", args) | ("b", args) => self.step_back(parse_count(args)?),
+            ("continue", []) | ("c", []) => self.run(),
+            ("break", [addr]) => {
+                let addr = parse_number(addr)?;
+                if addr >= self.program.get_instructions().len() {
+                    return Err(format!("address {} is outside of the program", addr));
+                }
+                self.wrist_comp.add_breakpoint(addr);
+            },
+            ("unbreak", [addr]) => {
+                if !self.wrist_comp.remove_breakpoint(parse_number(addr)?) {
+                    return Err(format!("no breakpoint set on address {}", addr));
+                }
+            },
+            ("watch", [reg]) => {
+                let reg = self.parse_register(reg)?;
+                self.wrist_comp.add_watchpoint(reg, WatchCondition::Changed).map_err(|err| err.to_string())?;
+            },
+            ("unwatch", [reg]) => {
+                let reg = self.parse_register(reg)?;
+                if !self.wrist_comp.remove_watchpoint(reg, WatchCondition::Changed) {
+                    return Err(format!("no watchpoint set on register {}", reg));
+                }
+            },
+            ("set", [reg, val]) => {
+                let reg = self.parse_register(reg)?;
+                let val = parse_number(val)?;
+                // Register bound to the instruction pointer is overwritten by it on the next step
+                if self.program.get_ip_reg() == Some(reg) {
+                    self.wrist_comp.set_ip(val);
+                    println!("Moved instruction pointer to address {}", val);
+                } else {
+                    self.wrist_comp.set_register(reg, val);
+                }
+                self.print_registers();
+            },
+            ("print", []) | ("p", []) => self.print_registers(),
+            ("disassemble", []) | ("d", []) => self.print_disassembly(),
+            ("reset", []) => {
+                self.wrist_comp = Debugger::new_wrist_computer(&self.program);
+                self.print_registers();
+            },
+            ("quit", []) | ("q", []) => return Ok(false),
+            ("help", []) | ("h", []) => println!("{}", HELP),
+            _ => return Err(format!("unrecognised command \"{}\" - enter \"help\" for the list of commands", line.trim())),
+        }
+        return Ok(true);
+    }
+
+    fn step(&mut self, count: usize) {
+        for _ in 0..count {
+            match self.wrist_comp.try_step(self.program.get_instructions()) {
+                Ok(step) => {
+                    println!("{}", disassemble_line(self.program.get_instructions(), step.ip, self.program.get_ip_reg()));
+                },
+                Err(err) => {
+                    println!("Stopped: {}", err);
+                    break;
+                }
+            }
+        }
+        self.print_registers();
+    }
+
+    fn step_back(&mut self, count: usize) {
+        for _ in 0..count {
+            if !self.wrist_comp.step_back() {
+                println!("Stopped: no more history");
+                break;
+            }
+        }
+        self.print_registers();
+    }
+
+    fn run(&mut self) {
+        if self.wrist_comp.get_ip() >= self.program.get_instructions().len() {
+            println!("Program has halted - enter \"reset\" to restart it");
+            return;
+        }
+        match self.wrist_comp.try_run_until_breakpoint(self.program.get_instructions()) {
+            Ok(StopReason::Halted) => println!("Program halted"),
+            Ok(StopReason::Breakpoint(addr)) => println!("Stopped at breakpoint on address {}", addr),
+            Ok(StopReason::Watchpoint(watchpoint)) => println!("Stopped: register {} changed", watchpoint.reg),
+            Err(err) => println!("Stopped: {}", err),
+        }
+        self.print_registers();
+    }
+
+    fn print_registers(&self) {
+        let registers = self.wrist_comp.get_registers().iter().enumerate()
+            .map(|(i, val)| format!("r{}={}", i, val)).collect::<Vec<String>>();
+        println!("ip={} steps={} {}", self.wrist_comp.get_ip(), self.wrist_comp.get_steps(), registers.join(" "));
+    }
+
+    fn print_disassembly(&self) {
+        let instructions = self.program.get_instructions();
+        let ip = self.wrist_comp.get_ip();
+        let start = ip.saturating_sub(DISASM_CONTEXT);
+        let end = (ip + DISASM_CONTEXT + 1).min(instructions.len());
+        for addr in start..end {
+            let marker = if addr == ip { "=>" } else { "  " };
+            println!("{} {}", marker, disassemble_line(instructions, addr, self.program.get_ip_reg()));
+        }
+    }
+
+    fn parse_register(&self, token: &str) -> Result<usize, String> {
+        let reg = parse_number(token.trim_start_matches('r'))?;
+        if reg >= self.wrist_comp.get_register_count() {
+            return Err(format!("register {} does not exist", reg));
+        }
+        return Ok(reg);
+    }
+}
+
+fn parse_number(token: &str) -> Result<usize, String> {
+    return token.parse::<usize>().map_err(|_| format!("invalid number \"{}\"", token));
+}
+
+/// Parses the optional repeat count given to a command, defaulting to 1.
+fn parse_count(args: &[&str]) -> Result<usize, String> {
+    match args {
+        [] => return Ok(1),
+        [count] => return parse_number(count),
+        _ => return Err(String::from("too many arguments")),
+    }
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() != 2 {
+        eprintln!("Usage: {} <program file>", args[0]);
+        process::exit(2);
+    }
+    let input = match fs::read_to_string(&args[1]) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("Failed to read \"{}\": {}", args[1], err);
+            process::exit(1);
+        }
+    };
+    let program = match input.parse::<Program>() {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("Failed to parse \"{}\": {}", args[1], err);
+            process::exit(1);
+        }
+    };
+    let mut debugger = match Debugger::new(program) {
+        Ok(debugger) => debugger,
+        Err(err) => {
+            eprintln!("Cannot debug \"{}\": {}", args[1], err);
+            process::exit(2);
+        }
+    };
+    println!("Loaded {} instructions from \"{}\" - enter \"help\" for the list of commands",
+        debugger.program.get_instructions().len(), args[1]);
+    debugger.print_disassembly();
+    let stdin = io::stdin();
+    loop {
+        print!("(wristdbg) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(err) => {
+                eprintln!("Failed to read command: {}", err);
+                break;
+            }
+        }
+        match debugger.execute_command(&line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(err) => println!("Error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_ip_register() {
+        let program = "#ip 1\nseti 5 0 0\nseti 7 0 0\nseti 9 0 0".parse::<Program>().unwrap();
+        let mut debugger = Debugger::new(program).unwrap();
+        assert_eq!(Ok(true), debugger.execute_command("set 1 2"));
+        assert_eq!(2, debugger.wrist_comp.get_ip());
+        assert_eq!(Ok(true), debugger.execute_command("step"));
+        assert_eq!(9, debugger.wrist_comp.get_register(0));
+        assert_eq!(2, debugger.wrist_comp.get_register(1));
+        assert_eq!(Ok(true), debugger.execute_command("set 0 4"));
+        assert_eq!(4, debugger.wrist_comp.get_register(0));
+    }
+}
@@ -1,8 +1,12 @@
 use regex::Regex;
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 
+use super::utils::map::MapRenderer;
 use super::utils::map::Point2D;
+use super::utils::map::RenderTile;
+use super::utils::map::Rgb;
 
 /// This struct is used to represent a rescue Light Point, as described in AoC 2018 Day 10.
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
@@ -28,6 +32,20 @@ impl LightPoint {
     }
 }
 
+impl RenderTile for LightPoint {
+    fn get_char(&self) -> char {
+        return '*';
+    }
+
+    fn get_colour(&self) -> Rgb {
+        return Rgb::WHITE;
+    }
+
+    fn get_empty_char() -> char {
+        return ' ';
+    }
+}
+
 /// This struct is used to contain all Light Points observed and manage the simulation of future
 /// positions.
 struct LightPointSim {
@@ -75,25 +93,11 @@ impl LightPointSim {
             .collect::<HashSet<Point2D>>();
     }
 
-    pub fn display_points(&self) {
-        // Get all unique points, and the min and max in x- and y-directions
-        let uniq_pos = self.get_uniq_pos();
-        let min_x = uniq_pos.iter().map(|pos| pos.pos_x).min().unwrap();
-        let max_x = uniq_pos.iter().map(|pos| pos.pos_x).max().unwrap();
-        let min_y = uniq_pos.iter().map(|pos| pos.pos_y).min().unwrap();
-        let max_y = uniq_pos.iter().map(|pos| pos.pos_y).max().unwrap();
-        // Iterate over all points within bounds
-        for pos_y in min_y..(max_y + 1) {
-            for pos_x in min_x..(max_x + 1) {
-                let c_point = Point2D::new(pos_x, pos_y);
-                if uniq_pos.contains(&c_point) {
-                    print!("*");
-                } else {
-                    print!(" ");
-                }
-            }
-            println!("");
-        }
+    /// Renders the current positions of the light points as text, over the smallest region
+    /// containing all of them.
+    pub fn render_points(&self) -> String {
+        let point_map = self.points.iter().map(|point| (point.pos, *point)).collect::<HashMap<Point2D, LightPoint>>();
+        return MapRenderer::new(&point_map).render_string();
     }
 
     pub fn calculate_box_size(&self) -> i64 {
@@ -138,7 +142,7 @@ fn solve_part_1(input: &LightPointSim) -> i64 {
         let box_size = light_point_sim.calculate_box_size();
         if box_size < 1000 {
             println!("DAY 10 PART 1 - SOLUTION");
-            light_point_sim.display_points();
+            print!("{}", light_point_sim.render_points());
             return -1;
         }
     }
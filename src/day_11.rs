@@ -1,23 +1,19 @@
-use std::collections::HashMap;
-
+use super::utils::map::Grid;
 use super::utils::map::Point2D;
 
+/// Width and height of the fuel cell grid.
+const GRID_SIZE: usize = 300;
+
 #[aoc_generator(day11)]
-fn generate_input(input: &str) -> HashMap<Point2D, i64> {
+fn generate_input(input: &str) -> Grid<i64> {
     let serial_num = input.trim().parse::<i64>().unwrap();
-    let mut cell_grid = HashMap::<Point2D, i64>::new();
-    for pos_y in 1..301 {
-        for pos_x in 1..301 {
-            let cell_loc = Point2D::new(pos_x, pos_y);
-            let power_level = calculate_power_level(serial_num, &cell_loc);
-            cell_grid.insert(cell_loc, power_level);
-        }
-    }
-    return cell_grid;
+    // Fuel cells are numbered from 1 in both axes
+    let origin = Point2D::new(1, 1);
+    return Grid::from_fn(origin, GRID_SIZE, GRID_SIZE, |cell_loc| calculate_power_level(serial_num, &cell_loc));
 }
 
 #[aoc(day11, part1)]
-fn solve_part_1(cell_grid: &HashMap<Point2D, i64>) -> String {
+fn solve_part_1(cell_grid: &Grid<i64>) -> String {
     let mut max_power_seen = 0;
     let mut max_top_left = Point2D::new(-1, -1);
     for pos_y in 1..299 {
@@ -36,7 +32,7 @@ fn solve_part_1(cell_grid: &HashMap<Point2D, i64>) -> String {
 }
 
 #[aoc(day11, part2)]
-fn solve_part_2(cell_grid: &HashMap<Point2D, i64>) -> String {
+fn solve_part_2(cell_grid: &Grid<i64>) -> String {
     let mut max_power_seen = 0;
     let mut max_top_left = Point2D::new(-1, -1);
     let mut size_of_max = 0; // Width of square providing largest power sum
@@ -77,7 +73,7 @@ fn solve_part_2(cell_grid: &HashMap<Point2D, i64>) -> String {
 }
 
 fn find_subgrid_power(
-    cell_grid: &HashMap<Point2D, i64>,
+    cell_grid: &Grid<i64>,
     width: i64,
     last_sub_power: i64,
     top_left: &Point2D,
@@ -89,7 +85,7 @@ fn find_subgrid_power(
         for delta_y in 0..width {
             for delta_x in 0..width {
                 let test_point = top_left.move_point(delta_x, delta_y);
-                subgrid_power += cell_grid.get(&test_point).unwrap();
+                subgrid_power += cell_grid[test_point];
             }
         }
         return subgrid_power;
@@ -101,8 +97,8 @@ fn find_subgrid_power(
             // Calculate points for powers to subtract and add
             let subtract_point = Point2D::new(top_left.pos_x - 1, pos_y);
             let add_point = Point2D::new(last_x, pos_y);
-            subgrid_power -= cell_grid.get(&subtract_point).unwrap();
-            subgrid_power += cell_grid.get(&add_point).unwrap();
+            subgrid_power -= cell_grid[subtract_point];
+            subgrid_power += cell_grid[add_point];
         }
         return subgrid_power;
     }
@@ -1,7 +1,18 @@
+use super::utils::animate::Animator;
+use super::utils::animate::Simulation;
 use super::utils::map::Direction;
+use super::utils::map::Grid;
+use super::utils::map::MapEntity;
+use super::utils::map::MapRenderer;
 use super::utils::map::Point2D;
+use super::utils::map::RenderTile;
+use super::utils::map::Rgb;
+use super::utils::map::parse_map_padded;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum CartTurnDirection {
@@ -12,12 +23,51 @@ enum CartTurnDirection {
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum TrackElement {
-    TrackStraight,
+    NoTrack,
+    TrackHorizontal,
+    TrackVertical,
     TrackCornerLeftSlant,
     TrackCornerRightSlant,
     TrackIntersection,
 }
 
+impl TryFrom<char> for TrackElement {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            ' ' => return Ok(TrackElement::NoTrack),
+            '-' => return Ok(TrackElement::TrackHorizontal),
+            '|' => return Ok(TrackElement::TrackVertical),
+            '+' => return Ok(TrackElement::TrackIntersection),
+            '\\' => return Ok(TrackElement::TrackCornerLeftSlant),
+            '/' => return Ok(TrackElement::TrackCornerRightSlant),
+            _ => return Err(()),
+        }
+    }
+}
+
+impl RenderTile for TrackElement {
+    fn get_char(&self) -> char {
+        match self {
+            TrackElement::NoTrack => return ' ',
+            TrackElement::TrackHorizontal => return '-',
+            TrackElement::TrackVertical => return '|',
+            TrackElement::TrackCornerLeftSlant => return '\\',
+            TrackElement::TrackCornerRightSlant => return '/',
+            TrackElement::TrackIntersection => return '+',
+        }
+    }
+
+    fn get_colour(&self) -> Rgb {
+        match self {
+            TrackElement::NoTrack => return Rgb::BLACK,
+            TrackElement::TrackIntersection => return Rgb::new(255, 255, 0),
+            _ => return Rgb::new(128, 128, 128),
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 struct CropCart {
     next_turn_dir: CartTurnDirection,
@@ -48,9 +98,29 @@ impl CropCart {
     }
 }
 
+impl TryFrom<char> for CropCart {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        return Direction::from_arrow(c).map(CropCart::new).ok_or(());
+    }
+}
+
+impl MapEntity for CropCart {
+    type Tile = TrackElement;
+
+    /// Carts always start on straight sections of track, running in the direction they face.
+    fn get_tile_beneath(&self) -> TrackElement {
+        match self.direction {
+            Direction::North | Direction::South => return TrackElement::TrackVertical,
+            Direction::East | Direction::West => return TrackElement::TrackHorizontal,
+        }
+    }
+}
+
 struct CartMap {
     crop_carts: HashMap<Point2D, Vec<CropCart>>,
-    track_map: HashMap<Point2D, TrackElement>,
+    track_map: Grid<TrackElement>,
     crash_sites: Vec<Point2D>,
 }
 
@@ -58,7 +128,7 @@ impl CartMap {
     /// Creates a new CartMap from the given initial crop carts and
     pub fn new(
         init_crop_carts: HashMap<Point2D, Vec<CropCart>>,
-        init_track_map: HashMap<Point2D, TrackElement>,
+        init_track_map: Grid<TrackElement>,
     ) -> Self {
         Self {
             crop_carts: init_crop_carts,
@@ -78,47 +148,11 @@ impl CartMap {
                 }
                 map
             },
-            track_map: {
-                let mut map = HashMap::<Point2D, TrackElement>::new();
-                for (k, v) in self.track_map.iter() {
-                    map.insert(*k, *v);
-                }
-                map
-            },
+            track_map: self.track_map.clone(),
             crash_sites: vec![],
         }
     }
 
-    /// Gets the current direction of the cart at the given location. None is returned if a cart is
-    /// not present at the given location.
-    fn get_cart_direction(&self, location: Point2D) -> Option<Direction> {
-        if !self.crop_carts.contains_key(&location) {
-            return None;
-        }
-        return Some(self.crop_carts.get(&location).unwrap()[0].direction);
-    }
-
-    /// Gets the next point the cart at the given location would be in if it moved one unit in its
-    /// current direction.
-    ///
-    /// This function calls panic! if the given location does not contain a crop cart.
-    fn get_shifted_point(&self, location: Point2D) -> Point2D {
-        // Get cart direction
-        let cart_direction_opt = self.get_cart_direction(location);
-        // Get if we have valid cart direction
-        if cart_direction_opt == None {
-            panic!("PANIC - Day 13 - tried to get direction of non-existent cart.");
-        }
-        let cart_direction = cart_direction_opt.unwrap();
-        // Calculate next point based on current direction
-        match cart_direction {
-            Direction::North => location.move_point(0, -1),
-            Direction::South => location.move_point(0, 1),
-            Direction::East => location.move_point(1, 0),
-            Direction::West => location.move_point(-1, 0),
-        }
-    }
-
     /// Adds the given location to the list of crash sites.
     fn add_crash_site(&mut self, crash_site: Point2D) {
         self.crash_sites.push(crash_site);
@@ -160,49 +194,26 @@ impl CartMap {
             if !self.crop_carts.contains_key(&start_point) {
                 continue;
             }
-            let track_element = self.track_map.get(&start_point).unwrap();
-            let new_point = match track_element {
-                TrackElement::TrackStraight => self.get_shifted_point(start_point),
-                TrackElement::TrackIntersection => {
-                    // Rotate the cart direction
-                    self.crop_carts.get_mut(&start_point).unwrap()[0].rotate_next_turn_direction();
-                    self.get_shifted_point(start_point)
-                },
+            // Turn the cart according to the track it is on, then move it one step forward
+            let cart = &mut self.crop_carts.get_mut(&start_point).unwrap()[0];
+            match self.track_map[start_point] {
+                TrackElement::NoTrack => panic!("Day 13 - cart ran off the track at {}.", start_point),
+                TrackElement::TrackHorizontal | TrackElement::TrackVertical => (),
+                TrackElement::TrackIntersection => cart.rotate_next_turn_direction(),
                 TrackElement::TrackCornerLeftSlant => {
-                    match self.crop_carts.get_mut(&start_point).unwrap()[0].direction {
-                        Direction::North => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::West;
-                        },
-                        Direction::South => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::East;
-                        },
-                        Direction::East => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::South;
-                        },
-                        Direction::West => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::North;
-                        }
-                    }
-                    self.get_shifted_point(start_point)
+                    cart.direction = match cart.direction {
+                        Direction::North | Direction::South => cart.direction.turn_left(),
+                        Direction::East | Direction::West => cart.direction.turn_right(),
+                    };
                 },
                 TrackElement::TrackCornerRightSlant => {
-                    match self.crop_carts.get_mut(&start_point).unwrap()[0].direction {
-                        Direction::North => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::East;
-                        },
-                        Direction::South => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::West;
-                        },
-                        Direction::East => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::North;
-                        },
-                        Direction::West => {
-                            self.crop_carts.get_mut(&start_point).unwrap()[0].direction = Direction::South;
-                        }
-                    }
-                    self.get_shifted_point(start_point)
-                }
-            };
+                    cart.direction = match cart.direction {
+                        Direction::North | Direction::South => cart.direction.turn_right(),
+                        Direction::East | Direction::West => cart.direction.turn_left(),
+                    };
+                },
+            }
+            let new_point = start_point.step(cart.direction);
             // Check if new point already has a cart
             if self.crop_carts.contains_key(&new_point) {
                 // Remove carts involved in crash
@@ -225,77 +236,46 @@ impl CartMap {
     }
 }
 
-#[aoc_generator(day13)]
-fn generate_input(input: &str) -> CartMap {
-    // First read file characters into a 2D array
-    let mut map_chars: Vec<Vec<char>> = vec![];
-    for line in input.lines() {
-        if line.len() == 0 {
-            continue;
-        }
-        let mut new_line_chars: Vec<char> = vec![];
-        for c in line.chars() {
-            new_line_chars.push(c);
-        }
-        map_chars.push(new_line_chars);
+impl Simulation for CartMap {
+    fn tick(&mut self) {
+        self.tick_along_carts(false);
     }
-    // Process each map char into the map
-    let mut track_map: HashMap<Point2D, TrackElement> = HashMap::new();
-    let mut crop_carts: HashMap<Point2D, Vec<CropCart>> = HashMap::new();
-    for pos_y in 0..map_chars.len() {
-        for pos_x in 0..map_chars[pos_y].len() {
-            let current_loc = Point2D::new(pos_x as i64, pos_y as i64);
-            match map_chars[pos_y][pos_x] {
-                ' ' => {
-                    // Blank space - ignore
-                    continue;
-                }
-                '-' => {
-                    // Straight track element - horizontal
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                }
-                '|' => {
-                    // Straight track element - vertical
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                }
-                '+' => {
-                    // Track intersection
-                    track_map.insert(current_loc, TrackElement::TrackIntersection);
-                }
-                'v' => {
-                    // South-bound cart
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                    crop_carts.insert(current_loc, vec![CropCart::new(Direction::South)]);
-                }
-                '^' => {
-                    // North-bound cart
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                    crop_carts.insert(current_loc, vec![CropCart::new(Direction::North)]);
-                }
-                '<' => {
-                    // West-bound cart
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                    crop_carts.insert(current_loc, vec![CropCart::new(Direction::West)]);
-                }
-                '>' => {
-                    // East-bound cart
-                    track_map.insert(current_loc, TrackElement::TrackStraight);
-                    crop_carts.insert(current_loc, vec![CropCart::new(Direction::East)]);
-                }
-                '\\' => {
-                    // Left-slant corner
-                    track_map.insert(current_loc, TrackElement::TrackCornerLeftSlant);
-                }
-                '/' => {
-                    // Right-slant corner
-                    track_map.insert(current_loc, TrackElement::TrackCornerRightSlant);
-                }
-                _ => {
-                    panic!("Day 13 generator - should not get here!");
-                }
-            }
+
+    /// Carts keep moving until at most one is left uncrashed.
+    fn is_finished(&self) -> bool {
+        return self.get_cart_count() <= 1;
+    }
+}
+
+impl fmt::Display for CartMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Carts are drawn over the track they are on, and crash sites over the top of everything
+        let mut renderer = MapRenderer::new(&self.track_map);
+        for (loc, carts) in self.crop_carts.iter() {
+            renderer = renderer.with_marker(*loc, carts[0].direction.to_arrow(), Rgb::new(0, 255, 0));
+        }
+        for loc in self.crash_sites.iter() {
+            renderer = renderer.with_marker(*loc, 'X', Rgb::new(255, 0, 0));
         }
+        return write!(f, "{}", renderer.render_string());
     }
+}
+
+/// Animates the carts moving around the tracks in the given raw map until one cart is left.
+/// Returns the number of ticks conducted.
+pub fn animate(input: &str, animator: &mut Animator<io::Stdout>) -> io::Result<u64> {
+    let mut cart_map = generate_input(input);
+    return animator.run(&mut cart_map, |cart_map| cart_map.to_string());
+}
+
+#[aoc_generator(day13)]
+fn generate_input(input: &str) -> CartMap {
+    // Lines may have trailing spaces trimmed, so pad them back out with empty space
+    let (track_map, carts) = match parse_map_padded::<TrackElement, CropCart>(input, TrackElement::NoTrack) {
+        Ok(parsed) => parsed,
+        Err(e) => panic!("Day 13 - invalid map at {}.", e),
+    };
+    let crop_carts = carts.into_iter().map(|(loc, cart)| (loc, vec![cart])).collect::<HashMap<Point2D, Vec<CropCart>>>();
     return CartMap::new(crop_carts, track_map);
 }
 
@@ -328,3 +308,30 @@ fn solve_part_2(input: &CartMap) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_d13_p1_proper() {
+        let input = generate_input(&std::fs::read_to_string("./input/2018/day13.txt").unwrap());
+        let result = solve_part_1(&input);
+        assert_eq!("8,9", result);
+    }
+
+    #[test]
+    fn test_d13_p2_proper() {
+        let input = generate_input(&std::fs::read_to_string("./input/2018/day13.txt").unwrap());
+        let result = solve_part_2(&input);
+        assert_eq!("73,33", result);
+    }
+
+    #[test]
+    fn test_d13_p2_example_trimmed() {
+        // Example with trailing spaces removed, leaving lines of different lengths
+        let input = generate_input("/>-<\\\n|   |\n| /<+-\\\n| | | v\n\\>+</ |\n  |   ^\n  \\<->/");
+        let result = solve_part_2(&input);
+        assert_eq!("6,4", result);
+    }
+}
@@ -1,9 +1,20 @@
+use super::utils::animate::Animator;
+use super::utils::animate::Simulation;
+use super::utils::map::Grid;
+use super::utils::map::MapEntity;
+use super::utils::map::MapRenderer;
 use super::utils::map::Point2D;
+use super::utils::map::RenderTile;
+use super::utils::map::Rgb;
+use super::utils::map::parse_map;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::cmp;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
 enum UnitVariant {
@@ -65,6 +76,26 @@ impl CombatUnit {
     }
 }
 
+impl TryFrom<char> for CombatUnit {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            'G' => return Ok(CombatUnit::new(UnitVariant::Goblin)),
+            'E' => return Ok(CombatUnit::new(UnitVariant::Elf)),
+            _ => return Err(()),
+        }
+    }
+}
+
+impl MapEntity for CombatUnit {
+    type Tile = MapTileType;
+
+    fn get_tile_beneath(&self) -> MapTileType {
+        return MapTileType::Space;
+    }
+}
+
 //
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
@@ -73,8 +104,36 @@ enum MapTileType {
     Space,
 }
 
+impl TryFrom<char> for MapTileType {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '#' => return Ok(MapTileType::Wall),
+            '.' => return Ok(MapTileType::Space),
+            _ => return Err(()),
+        }
+    }
+}
+
+impl RenderTile for MapTileType {
+    fn get_char(&self) -> char {
+        match self {
+            MapTileType::Wall => return '#',
+            MapTileType::Space => return '.',
+        }
+    }
+
+    fn get_colour(&self) -> Rgb {
+        match self {
+            MapTileType::Wall => return Rgb::new(64, 64, 64),
+            MapTileType::Space => return Rgb::new(200, 200, 200),
+        }
+    }
+}
+
 struct CombatMap {
-    map: HashMap<Point2D, MapTileType>,
+    map: Grid<MapTileType>,
     unit_locations: HashMap<Point2D, CombatUnit>,
     full_rounds_compl: u64,
     combat_finished: bool,
@@ -94,40 +153,12 @@ impl CombatMap {
 
     /// Creates a new CombatMap from the given raw map.
     pub fn new(raw_map: &str) -> Self {
-        let mut map = HashMap::<Point2D, MapTileType>::new();
-        let mut units = HashMap::<Point2D, CombatUnit>::new();
-        let lines = raw_map.lines();
-        let mut y = 0;
-        let mut x = 0;
-        // Process each line in raw map, by character
-        for line in lines {
-            for tile in line.chars() {
-                let loc = Point2D::new(x, y);
-                match tile {
-                    '#' => {
-                        map.insert(loc, MapTileType::Wall);
-                    }
-                    '.' => {
-                        map.insert(loc, MapTileType::Space);
-                    }
-                    'G' => {
-                        map.insert(loc, MapTileType::Space);
-                        units.insert(loc, CombatUnit::new(UnitVariant::Goblin));
-                    }
-                    'E' => {
-                        map.insert(loc, MapTileType::Space);
-                        units.insert(loc, CombatUnit::new(UnitVariant::Elf));
-                    }
-                    _ => panic!("Day15 - invalid map tile character."),
-                }
-                x += 1;
-            }
-            // Go to the next line
-            y += 1;
-            x = 0;
-        }
+        let (map, units) = match parse_map::<MapTileType, CombatUnit>(raw_map) {
+            Ok(parsed) => parsed,
+            Err(e) => panic!("Day15 - invalid map at {}.", e),
+        };
         Self {
-            unit_locations: units,
+            unit_locations: units.into_iter().collect(),
             map: map,
             full_rounds_compl: 0,
             combat_finished: false,
@@ -423,6 +454,37 @@ impl CombatMap {
 
 //
 
+impl fmt::Display for CombatMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Units are drawn over the top of the space they occupy
+        let mut renderer = MapRenderer::new(&self.map);
+        for (loc, unit) in self.unit_locations.iter() {
+            renderer = match unit.get_variant() {
+                UnitVariant::Goblin => renderer.with_marker(*loc, 'G', Rgb::new(0, 160, 0)),
+                UnitVariant::Elf => renderer.with_marker(*loc, 'E', Rgb::new(200, 0, 0)),
+            };
+        }
+        return write!(f, "{}", renderer.render_string());
+    }
+}
+
+impl Simulation for CombatMap {
+    fn tick(&mut self) {
+        self.conduct_turn();
+    }
+
+    fn is_finished(&self) -> bool {
+        return self.is_combat_finished();
+    }
+}
+
+/// Animates the combat on the given raw map, one round per tick, until one side is defeated.
+/// Returns the number of ticks conducted.
+pub fn animate(input: &str, animator: &mut Animator<io::Stdout>) -> io::Result<u64> {
+    let mut combat_map = generate_input(input);
+    return animator.run(&mut combat_map, |combat_map| combat_map.to_string());
+}
+
 #[aoc_generator(day15)]
 fn generate_input(input: &str) -> CombatMap {
     return CombatMap::new(input);
@@ -1,46 +1,10 @@
 use regex::Regex;
 
-use std::collections::HashMap;
-use std::collections::HashSet;
-
 use super::utils::wristcomp::Instruction;
-use super::utils::wristcomp::Operation;
+use super::utils::wristcomp::OpcodeInference;
+use super::utils::wristcomp::OpSample;
 use super::utils::wristcomp::WristComputer;
 
-use enum_iterator::IntoEnumIterator;
-
-struct OpSample {
-    reg_before: Vec<usize>,
-    reg_after: Vec<usize>,
-    instruction: Vec<usize>
-}
-
-impl OpSample {
-    pub fn new(reg_before: Vec<usize>, reg_after: Vec<usize>, instruction: Vec<usize>) -> Self {
-        Self {
-            reg_before: reg_before,
-            reg_after: reg_after,
-            instruction: instruction
-        }
-    }
-
-    pub fn get_opcode(&self) -> usize {
-        return self.instruction[0];
-    }
-
-    pub fn get_reg_before(&self) -> Vec<usize> {
-        return self.reg_before.clone();
-    }
-    
-    pub fn get_reg_after(&self) -> Vec<usize> {
-        return self.reg_after.clone();
-    }
-
-    pub fn get_values(&self) -> (usize, usize, usize) {
-        return (self.instruction[1], self.instruction[2], self.instruction[3]);
-    }
-}
-
 #[aoc_generator(day16)]
 fn generate_input(input: &str) -> (Vec<OpSample>, Vec<Vec<usize>>) {
     // Create iterator to read lines from input
@@ -93,27 +57,11 @@ fn generate_input(input: &str) -> (Vec<OpSample>, Vec<Vec<usize>>) {
 
 #[aoc(day16, part1)]
 fn solve_part_1(input: &(Vec<OpSample>, Vec<Vec<usize>>)) -> u64 {
+    // Count samples that behave like at least 3 operations
     let mut total_count = 0;
-    // Check each operation sample
     for samp in &input.0 {
-        let mut output_match_count = 0;
-        let reg_before = samp.get_reg_before();
-        let reg_after = samp.get_reg_after();
-        let values = samp.get_values();
-        // Try each operation
-        for op in Operation::into_enum_iter() {
-            let instruction = Instruction::new(op, values);
-            // Perform operation and check if output matches output from operation sample
-            let op_output = WristComputer::perform_operation(&reg_before, &instruction);
-            let output_match = check_vectors_equal_elements(&reg_after, &op_output);
-            if output_match {
-                output_match_count += 1;
-            }
-            // Increment overall count if we find it matches at least 3 operations
-            if output_match_count == 3 {
-                total_count += 1;
-                break;
-            }
+        if samp.get_matching_operations().len() >= 3 {
+            total_count += 1;
         }
     }
     return total_count;
@@ -121,29 +69,11 @@ fn solve_part_1(input: &(Vec<OpSample>, Vec<Vec<usize>>)) -> u64 {
 
 #[aoc(day16, part2)]
 fn solve_part_2(input: &(Vec<OpSample>, Vec<Vec<usize>>)) -> usize {
-    // Determine mapping of opcodes to operations
-    let mut opcode_poss = HashMap::<usize, HashSet<Operation>>::new();
-    for i in 0..16 {
-        opcode_poss.insert(i, HashSet::<Operation>::new());
-    }
-    // Process each operation sample
-    for samp in &input.0 {
-        let reg_before = samp.get_reg_before();
-        let reg_after = samp.get_reg_after();
-        let values = samp.get_values();
-        for op in Operation::into_enum_iter() {
-            let instruction = Instruction::new(op, values);
-            // Perform operation and check if output matches sample output
-            let op_output = WristComputer::perform_operation(&reg_before, &instruction);
-            let output_match = check_vectors_equal_elements(&reg_after, &op_output);
-            // If output matches, add the operation as a possible mapping for the opcode
-            if output_match {
-                opcode_poss.get_mut(&samp.get_opcode()).unwrap().insert(op);
-            }
-        }
-    }
-    // Determine opcode to operation mapping by reducing possibilities using unique mappings
-    let opcode_mapping = determine_opcode_mappings(opcode_poss);
+    // Determine mapping of opcodes to operations consistent with the operation samples
+    let opcode_mapping = match OpcodeInference::new(&input.0).resolve() {
+        Ok(mapping) => mapping,
+        Err(err) => panic!("Day 16 - failed to determine opcode mapping: {}.", err),
+    };
     // Generate the test program
     let mut program: Vec<Instruction> = vec![];
     for instruct in &input.1 {
@@ -153,61 +83,16 @@ fn solve_part_2(input: &(Vec<OpSample>, Vec<Vec<usize>>)) -> usize {
         program.push(instruction);
     }
     // Execute the test program
-    let mut wrist_comp = WristComputer::new(None);
+    let mut wrist_comp = WristComputer::with_registers(None, 4);
     wrist_comp.execute_program(&program);
     // Return the value in register 0 after executing test program
     return wrist_comp.get_registers()[0];
 }
 
-fn check_vectors_equal_elements<T: PartialEq>(left: &Vec<T>, right: &Vec<T>) -> bool {
-    // First check if vectors are the same length
-    if left.len() != right.len() {
-        return false
-    }
-    for i in 0..left.len() {
-        if left[i] != right[i] {
-            return false;
-        }
-    }
-    return true;
-}
-
-fn determine_opcode_mappings(opcode_poss: HashMap<usize, HashSet<Operation>>) -> HashMap<usize, Operation> {
-    let mut opcode_mapping = HashMap::<usize, Operation>::new();
-    let mut opcode_counts = opcode_poss.clone();
-    loop {
-        // Check which opcodes have only one possible operation
-        let mut uniq_opcodes = Vec::<usize>::new();
-        for (opcode, possibles) in opcode_counts.iter() {
-            if possibles.len() == 1 {
-                uniq_opcodes.push(*opcode);
-            }
-        }
-        // Add mappings for uniq opcodes
-        for opcode in uniq_opcodes {
-            // Get the operation mapped to the opcode
-            let op = *opcode_counts.get(&opcode).unwrap().iter().next().unwrap();
-            opcode_mapping.insert(opcode, op);
-            // Remove the opcode from the opcode count
-            opcode_counts.remove(&opcode);
-        }
-        // Check if we have any move opcodes left to map
-        if opcode_counts.len() == 0 {
-            break;
-        }
-        // Remove unique opcodes from possibles in opcode counts
-        for (_opcode, possibles) in opcode_counts.iter_mut() {
-            for op in opcode_mapping.values() {
-                possibles.remove(op);
-            }
-        }
-    }
-    return opcode_mapping;
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::utils::wristcomp::minimise_samples;
 
     #[test]
     fn test_d16_p1_proper() {
@@ -222,4 +107,13 @@ mod tests {
         let result = solve_part_2(&input);
         assert_eq!(649, result);
     }
+
+    #[test]
+    fn test_d16_minimised_samples() {
+        let input = generate_input(&std::fs::read_to_string("./input/2018/day16.txt").unwrap());
+        let minimised = minimise_samples(&input.0).unwrap();
+        assert!(minimised.len() < input.0.len());
+        let minimised_input = (minimised, input.1.clone());
+        assert_eq!(649, solve_part_2(&minimised_input));
+    }
 }
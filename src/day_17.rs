@@ -1,30 +1,72 @@
 use std::fmt;
-use std::collections::HashMap;
+use std::io;
 use regex::Regex;
 
 use enum_iterator::IntoEnumIterator;
 
+use super::utils::animate::Animator;
+use super::utils::animate::Simulation;
+use super::utils::map::Grid;
+use super::utils::map::MapRenderer;
 use super::utils::map::Point2D;
+use super::utils::map::RenderTile;
+use super::utils::map::Rgb;
+
+/// Number of rows of the reservoir drawn in each frame of the animation.
+const ANIMATION_ROWS: i64 = 40;
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq, IntoEnumIterator)]
 enum MapTile {
+    Sand,
     Clay,
     WaterFlow,
     WaterRest
 }
 
+impl RenderTile for MapTile {
+    fn get_char(&self) -> char {
+        match self {
+            MapTile::Sand => return '.',
+            MapTile::Clay => return '#',
+            MapTile::WaterRest => return '~',
+            MapTile::WaterFlow => return '|',
+        }
+    }
+
+    fn get_colour(&self) -> Rgb {
+        match self {
+            MapTile::Sand => return MapTile::get_empty_colour(),
+            MapTile::Clay => return Rgb::new(139, 90, 43),
+            MapTile::WaterRest => return Rgb::new(0, 64, 255),
+            MapTile::WaterFlow => return Rgb::new(128, 192, 255),
+        }
+    }
+
+    /// Locations below the scanned region are drawn as sand.
+    fn get_empty_colour() -> Rgb {
+        return Rgb::new(237, 201, 175);
+    }
+}
+
+/// Step in the flow of water from a location, with steps for a location conducted in this order.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum FlowStep {
+    Fall,
+    Spread
+}
+
 struct ReservoirMap {
-    contents: HashMap<Point2D, MapTile>,
+    contents: Grid<MapTile>,
     min_y: i64,
     max_y: i64,
-    min_x: i64,
-    max_x: i64
+    pending: Vec<(Point2D, FlowStep)>,
+    flow_front: Point2D
 }
 
 impl ReservoirMap {
     /// Creates a new ReservourMap from the given raw map input.
     pub fn new(raw_map: &str) -> Self {
-        let mut contents = HashMap::<Point2D, MapTile>::new();
+        let mut clay_locs = Vec::<Point2D>::new();
         // Create regex to match two variants of line from raw input
         let x_range_regex = Regex::new(r"y=(\d+), x=(\d+)..(\d+)").unwrap();
         let y_range_regex = Regex::new(r"x=(\d+), y=(\d+)..(\d+)").unwrap();
@@ -44,8 +86,7 @@ impl ReservoirMap {
                     let x_high = capture[3].parse::<i64>().unwrap();
                     // Record new horizontal clay vein
                     for x in x_low..=x_high {
-                        let new_clay_loc = Point2D::new(x, y_loc);
-                        contents.insert(new_clay_loc, MapTile::Clay);
+                        clay_locs.push(Point2D::new(x, y_loc));
                     }
                     // Check in observed co-ord limits need to be updated
                     if y_loc > max_y {
@@ -71,8 +112,7 @@ impl ReservoirMap {
                     let y_high = capture[3].parse::<i64>().unwrap();
                     // Record new vertical clay vein
                     for y in y_low..=y_high {
-                        let new_clay_loc = Point2D::new(x_loc, y);
-                        contents.insert(new_clay_loc, MapTile::Clay);
+                        clay_locs.push(Point2D::new(x_loc, y));
                     }
                     if y_high > max_y {
                         max_y = y_high;
@@ -90,13 +130,24 @@ impl ReservoirMap {
                 }
             }
         }
-        // Create the new ReservoirMap from the data extracted from the raw input
+        // Cover from the spring down to the lowest clay, with room for water to fall past the
+        // outermost clay on either side
+        let origin = Point2D::new(min_x - 1, 0);
+        let width = (max_x - min_x + 3) as usize;
+        let height = (max_y + 1) as usize;
+        let mut contents = Grid::with_origin(origin, width, height, MapTile::Sand);
+        for loc in clay_locs {
+            contents[loc] = MapTile::Clay;
+        }
+        // Create the new ReservoirMap from the data extracted from the raw input, with water about
+        // to fall from the spring at (x:500, y:0)
+        let spring_loc = Point2D::new(500, 0);
         Self {
             contents: contents,
             min_y: min_y,
             max_y: max_y,
-            min_x: min_x - 1,
-            max_x: max_x + 1
+            pending: vec![(spring_loc, FlowStep::Fall)],
+            flow_front: spring_loc
         }
     }
 
@@ -106,17 +157,17 @@ impl ReservoirMap {
             contents: self.contents.clone(),
             min_y: self.min_y,
             max_y: self.max_y,
-            min_x: self.min_x,
-            max_x: self.max_x
+            pending: self.pending.clone(),
+            flow_front: self.flow_front
         }
     }
 
     /// Flows water from spring at (x:500, y:0) until all applicable spaces are filled with water
-    /// at rest or flowing water.
+    /// at rest or flowing water, continuing on from any steps already conducted.
     pub fn flow_water(&mut self) {
-        // Start with spring at (x:500, y:0)
-        let spring_loc = Point2D::new(500, 0);
-        self.flow_water_dfs(spring_loc);
+        while !self.pending.is_empty() {
+            self.conduct_flow_step();
+        }
     }
 
     /// Checks if the given location contains a tile that additional water can come to rest upon - 
@@ -130,23 +181,51 @@ impl ReservoirMap {
         return false;
     }
 
-    /// Flows water down from the starting node, using a modified depth-first search algorithm
-    /// (adapted to meet needs to puzzle).
-    fn flow_water_dfs(&mut self, node: Point2D) {
+    /// Conducts the next pending step of the flow of water down from the spring, using a modified
+    /// depth-first search algorithm (adapted to meet needs to puzzle). Pending steps are kept on a
+    /// stack rather than by recursion, so the flow can be paused between steps.
+    ///
+    /// Returns true if any tiles were changed by the step.
+    fn conduct_flow_step(&mut self) -> bool {
+        let (node, step) = match self.pending.pop() {
+            Some(next) => next,
+            None => return false,
+        };
+        self.flow_front = node;
+        match step {
+            FlowStep::Fall => return self.flow_water_down(node),
+            FlowStep::Spread => return self.flow_water_across(node),
+        }
+    }
+
+    /// Visits the node with flowing water, then flows down from it before spreading across.
+    /// Returns true if the node did not already have flowing water.
+    fn flow_water_down(&mut self, node: Point2D) -> bool {
         // base case - exceeded max depth
         if node.pos_y > self.max_y {
-            return;
+            return false;
         }
         // Initially visit current node by inserting a water flow tile
-        self.contents.insert(node, MapTile::WaterFlow);
+        let changed = self.contents[node] != MapTile::WaterFlow;
+        self.contents[node] = MapTile::WaterFlow;
+        // Spread across once any flow underneath has finished
+        self.pending.push((node, FlowStep::Spread));
         // Check if water can flow down - visit neighbour below
         let node_below = node.move_point(0, 1);
         if !self.check_if_full(node_below) {
-            self.flow_water_dfs(node_below);
+            self.pending.push((node_below, FlowStep::Fall));
         }
+        return changed;
+    }
+
+    /// Spreads water across from the node after flowing down from it, filling the row with water
+    /// at rest if it is bounded on both sides and otherwise flowing down past the unbounded sides.
+    /// Returns true if any tiles in the row were changed.
+    fn flow_water_across(&mut self, node: Point2D) -> bool {
         // base case - no rest water under current node even after flow underneath
+        let node_below = node.move_point(0, 1);
         if !self.check_if_full(node_below) {
-            return;
+            return false;
         // Water cannot flow down
         } else {
             // Check for left bound
@@ -188,23 +267,30 @@ impl ReservoirMap {
             // If water is bounded on left and right, fill row and back-track
             let x_left_bound = left_bel_node.pos_x;
             let x_right_bound = right_bel_node.pos_x;
+            let mut changed = false;
             // If left and right bounds valid, insert water at rest between bounds then backtrack
             if is_left_bounded && is_right_bounded {
                 for x in x_left_bound..=x_right_bound {
-                    self.contents.insert(Point2D::new(x, node.pos_y), MapTile::WaterRest);
+                    let tile = &mut self.contents[Point2D::new(x, node.pos_y)];
+                    changed |= *tile != MapTile::WaterRest;
+                    *tile = MapTile::WaterRest;
                 }
-                return;
+                return changed;
             // Not bounded, so insert flowing water then flow down from sides where needed
             } else {
                 for x in x_left_bound..=x_right_bound {
-                    self.contents.insert(Point2D::new(x, node.pos_y), MapTile::WaterFlow);
-                }
-                if !is_left_bounded {
-                    self.flow_water_dfs(left_bel_node.move_point(0, -1));
+                    let tile = &mut self.contents[Point2D::new(x, node.pos_y)];
+                    changed |= *tile != MapTile::WaterFlow;
+                    *tile = MapTile::WaterFlow;
                 }
+                // Right side is pushed first so the left side is flowed down from first
                 if !is_right_bounded {
-                    self.flow_water_dfs(right_bel_node.move_point(0, -1));
+                    self.pending.push((right_bel_node.move_point(0, -1), FlowStep::Fall));
                 }
+                if !is_left_bounded {
+                    self.pending.push((left_bel_node.move_point(0, -1), FlowStep::Fall));
+                }
+                return changed;
             }
         }
     }
@@ -213,8 +299,8 @@ impl ReservoirMap {
     pub fn get_tile_count(&self, tile_type: MapTile) -> u64 {
         let mut count: u64 = 0;
         for (loc, tile) in self.contents.iter() {
-            // Ignore tiles with Y co-ord outside of min and max values from scan results
-            if loc.pos_y < self.min_y || loc.pos_y > self.max_y {
+            // Ignore tiles with Y co-ord above min value from scan results
+            if loc.pos_y < self.min_y {
                 continue;
             }
             if *tile == tile_type {
@@ -225,32 +311,57 @@ impl ReservoirMap {
     }
 }
 
+impl ReservoirMap {
+    /// Creates a renderer covering the scanned region of the reservoir and the row below it, with
+    /// the spring of water drawn as '+'.
+    fn get_renderer(&self) -> MapRenderer<'_, Grid<MapTile>> {
+        let bottom_right = self.contents.get_bottom_right().move_point(0, 1);
+        return MapRenderer::new(&self.contents)
+            .with_bounds(self.contents.get_origin(), bottom_right)
+            .with_marker(Point2D::new(500, 0), '+', Rgb::new(0, 0, 128));
+    }
+
+    /// Renders the given number of rows of the reservoir as text, centred (where possible) on the
+    /// row where water most recently flowed.
+    fn render_flow_front(&self, rows: i64) -> String {
+        let bottom = self.contents.get_bottom_right().pos_y + 1;
+        let top = (self.flow_front.pos_y - rows / 2).min(bottom - rows + 1).max(0);
+        let top_left = Point2D::new(self.contents.get_origin().pos_x, top);
+        let bottom_right = Point2D::new(self.contents.get_bottom_right().pos_x, (top + rows - 1).min(bottom));
+        return self.get_renderer().with_bounds(top_left, bottom_right).render_string();
+    }
+}
+
 impl std::fmt::Display for ReservoirMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-        let mut output = String::new();
-        for y in 0..=self.max_y+1 {
-            for x in self.min_x..=self.max_x {
-                if x == 500 && y == 0 {
-                    output += "+";
-                    continue;
-                }
-                let loc = Point2D::new(x, y);
-                if let Some(tile) = self.contents.get(&loc) {
-                    match tile {
-                        MapTile::Clay => output += "#",
-                        MapTile::WaterRest => output += "~",
-                        MapTile::WaterFlow => output += "|",
-                    }
-                } else {
-                    output += ".";
-                }
+        return write!(f, "{}", self.get_renderer().render_string());
+    }
+}
+
+impl Simulation for ReservoirMap {
+    /// Conducts steps of the flow until one changes the map, skipping over steps that only retrace
+    /// water that is already flowing.
+    fn tick(&mut self) {
+        while !self.pending.is_empty() {
+            if self.conduct_flow_step() {
+                return;
             }
-            output += "\n";
         }
-        write!(f, "{}", output)
+    }
+
+    fn is_finished(&self) -> bool {
+        return self.pending.is_empty();
     }
 }
 
+/// Animates the water flowing from the spring in the reservoir given by the raw scan, one change to
+/// the map per tick. Only the rows around the flowing water are drawn, as the reservoir is too
+/// tall to fit in the terminal.
+pub fn animate(input: &str, animator: &mut Animator<io::Stdout>) -> io::Result<u64> {
+    let mut reservoir_map = generate_input(input);
+    return animator.run(&mut reservoir_map, |reservoir_map| reservoir_map.render_flow_front(ANIMATION_ROWS));
+}
+
 #[aoc_generator(day17)]
 fn generate_input(input: &str) -> ReservoirMap {
     return ReservoirMap::new(input);
@@ -293,4 +404,21 @@ mod tests {
         let result = solve_part_2(&input);
         assert_eq!(26030, result);
     }
+
+    #[test]
+    fn test_d17_example_ticked() {
+        let input = generate_input("x=495, y=2..7\ny=7, x=495..501\nx=501, y=3..7\nx=498, y=2..4\nx=506, y=1..2\nx=498, y=10..13\nx=504, y=10..13\ny=13, x=498..504");
+        assert_eq!(57, solve_part_1(&input));
+        assert_eq!(29, solve_part_2(&input));
+        // Ticking one step at a time gives the same result as flowing all at once
+        let mut reservoir_map = input.duplicate();
+        let mut ticks = 0;
+        while !reservoir_map.is_finished() {
+            reservoir_map.tick();
+            ticks += 1;
+        }
+        assert!(ticks > 1);
+        assert_eq!(29, reservoir_map.get_tile_count(MapTile::WaterRest));
+        assert_eq!(28, reservoir_map.get_tile_count(MapTile::WaterFlow));
+    }
 }
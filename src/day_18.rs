@@ -1,6 +1,16 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
 
+use super::utils::animate::Animator;
+use super::utils::animate::Simulation;
+use super::utils::map::Grid;
+use super::utils::map::MapRenderer;
 use super::utils::map::Point2D;
+use super::utils::map::RenderTile;
+use super::utils::map::Rgb;
+use super::utils::map::parse_grid;
 
 use enum_iterator::IntoEnumIterator;
 
@@ -11,6 +21,37 @@ enum LumberAreaTile {
     Lumberyard,
 }
 
+impl TryFrom<char> for LumberAreaTile {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => return Ok(LumberAreaTile::OpenGround),
+            '|' => return Ok(LumberAreaTile::Trees),
+            '#' => return Ok(LumberAreaTile::Lumberyard),
+            _ => return Err(()),
+        }
+    }
+}
+
+impl RenderTile for LumberAreaTile {
+    fn get_char(&self) -> char {
+        match self {
+            LumberAreaTile::OpenGround => return '.',
+            LumberAreaTile::Trees => return '|',
+            LumberAreaTile::Lumberyard => return '#',
+        }
+    }
+
+    fn get_colour(&self) -> Rgb {
+        match self {
+            LumberAreaTile::OpenGround => return Rgb::new(196, 164, 96),
+            LumberAreaTile::Trees => return Rgb::new(34, 139, 34),
+            LumberAreaTile::Lumberyard => return Rgb::new(110, 70, 30),
+        }
+    }
+}
+
 /// Creates a new HashMap that can be used to count how many of each tile type are in a LumberArea.
 fn get_blank_tile_count() -> HashMap<LumberAreaTile, u64> {
     let mut tile_counts = HashMap::<LumberAreaTile, u64>::new();
@@ -24,42 +65,19 @@ fn get_blank_tile_count() -> HashMap<LumberAreaTile, u64> {
 /// tile in the lumber area represents a one-acre-square area.
 struct LumberArea {
     minutes_elapsed: u64,
-    map: HashMap<Point2D, LumberAreaTile>,
+    map: Grid<LumberAreaTile>,
     tile_counts: HashMap<LumberAreaTile, u64>,
 }
 
 impl LumberArea {
     pub fn new(raw_input: &str) -> Self {
-        // Initialise the variables to hold data parsed from raw input
-        let mut map = HashMap::<Point2D, LumberAreaTile>::new();
+        let map: Grid<LumberAreaTile> = match parse_grid(raw_input) {
+            Ok(map) => map,
+            Err(e) => panic!("Day 18 - invalid map at {}.", e),
+        };
         let mut tile_counts = get_blank_tile_count();
-        // Process each line
-        let mut lines = raw_input.lines();
-        let mut y = 0;
-        let mut x;
-        while let Some(line) = lines.next() {
-            // Process each character from current line, adding tile to lumber area map
-            x = 0;
-            for c in line.chars() {
-                let loc = Point2D::new(x, y);
-                match c {
-                    '.' => {
-                        map.insert(loc, LumberAreaTile::OpenGround);
-                        *tile_counts.get_mut(&LumberAreaTile::OpenGround).unwrap() += 1;
-                    }
-                    '|' => {
-                        map.insert(loc, LumberAreaTile::Trees);
-                        *tile_counts.get_mut(&LumberAreaTile::Trees).unwrap() += 1;
-                    }
-                    '#' => {
-                        map.insert(loc, LumberAreaTile::Lumberyard);
-                        *tile_counts.get_mut(&LumberAreaTile::Lumberyard).unwrap() += 1;
-                    }
-                    _ => panic!("Day 18 - invalid map character."),
-                }
-                x += 1;
-            }
-            y += 1;
+        for tile in map.values() {
+            *tile_counts.get_mut(tile).unwrap() += 1;
         }
         Self {
             minutes_elapsed: 0,
@@ -84,54 +102,46 @@ impl LumberArea {
 
     fn get_surrounding_tile_count(&self, loc: Point2D) -> HashMap<LumberAreaTile, u64> {
         let mut tile_counts = get_blank_tile_count();
-        let surr_points = loc.get_surrounding_points();
-        for point in surr_points {
-            if let Some(tile) = self.map.get(&point) {
-                *tile_counts.get_mut(&tile).unwrap() += 1;
-            }
+        for (_point, tile) in self.map.surrounding(&loc) {
+            *tile_counts.get_mut(tile).unwrap() += 1;
         }
         return tile_counts;
     }
 
     pub fn simulate_next_minute(&mut self) {
-        // Initialise variables to hold updated state of lumber collection area
-        let mut new_map = HashMap::<Point2D, LumberAreaTile>::new();
+        // Initialise variable to hold updated tile counts of lumber collection area
         let mut new_tile_counts = get_blank_tile_count();
         // Visit each acre tile, determining what it will change to for the next minute
-        for (loc, tile) in self.map.iter() {
-            let surr_tile_counts = self.get_surrounding_tile_count(*loc);
-            match tile {
+        let new_map = self.map.map(|loc, tile| {
+            let surr_tile_counts = self.get_surrounding_tile_count(loc);
+            let new_tile = match tile {
                 LumberAreaTile::OpenGround => {
                     if *surr_tile_counts.get(&LumberAreaTile::Trees).unwrap() >= 3 {
-                        new_map.insert(*loc, LumberAreaTile::Trees);
-                        *new_tile_counts.get_mut(&LumberAreaTile::Trees).unwrap() += 1;
+                        LumberAreaTile::Trees
                     } else {
-                        new_map.insert(*loc, LumberAreaTile::OpenGround);
-                        *new_tile_counts.get_mut(&LumberAreaTile::OpenGround).unwrap() += 1;
+                        LumberAreaTile::OpenGround
                     }
                 }
                 LumberAreaTile::Trees => {
                     if *surr_tile_counts.get(&LumberAreaTile::Lumberyard).unwrap() >= 3 {
-                        new_map.insert(*loc, LumberAreaTile::Lumberyard);
-                        *new_tile_counts.get_mut(&LumberAreaTile::Lumberyard).unwrap() += 1;
+                        LumberAreaTile::Lumberyard
                     } else {
-                        new_map.insert(*loc, LumberAreaTile::Trees);
-                        *new_tile_counts.get_mut(&LumberAreaTile::Trees).unwrap() += 1;
+                        LumberAreaTile::Trees
                     }
                 }
                 LumberAreaTile::Lumberyard => {
                     if *surr_tile_counts.get(&LumberAreaTile::Lumberyard).unwrap() >= 1
                         && *surr_tile_counts.get(&LumberAreaTile::Trees).unwrap() >= 1
                     {
-                        new_map.insert(*loc, LumberAreaTile::Lumberyard);
-                        *new_tile_counts.get_mut(&LumberAreaTile::Lumberyard).unwrap() += 1;
+                        LumberAreaTile::Lumberyard
                     } else {
-                        new_map.insert(*loc, LumberAreaTile::OpenGround);
-                        *new_tile_counts.get_mut(&LumberAreaTile::OpenGround).unwrap() += 1;
+                        LumberAreaTile::OpenGround
                     }
                 }
-            }
-        }
+            };
+            *new_tile_counts.get_mut(&new_tile).unwrap() += 1;
+            return new_tile;
+        });
         // Update lumber area with new state
         self.minutes_elapsed += 1;
         self.map = new_map;
@@ -139,6 +149,25 @@ impl LumberArea {
     }
 }
 
+impl fmt::Display for LumberArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", MapRenderer::new(&self.map).render_string());
+    }
+}
+
+impl Simulation for LumberArea {
+    fn tick(&mut self) {
+        self.simulate_next_minute();
+    }
+}
+
+/// Animates the lumber area given by the raw map, one minute per tick. The animation only stops
+/// when quit.
+pub fn animate(input: &str, animator: &mut Animator<io::Stdout>) -> io::Result<u64> {
+    let mut lumber_area = generate_input(input);
+    return animator.run(&mut lumber_area, |lumber_area| lumber_area.to_string());
+}
+
 #[aoc_generator(day18)]
 fn generate_input(input: &str) -> LumberArea {
     return LumberArea::new(input);
@@ -1,81 +1,67 @@
-use regex::Regex;
-
+use super::utils::wristcomp::ControlFlowGraph;
+use super::utils::wristcomp::Program;
+use super::utils::wristcomp::StopReason;
 use super::utils::wristcomp::WristComputer;
-use super::utils::wristcomp::Instruction;
-use super::utils::wristcomp::Operation;
 
 #[aoc_generator(day19)]
-fn generate_input(input: &str) -> (usize, Vec<Instruction>) {
-    let ip_regex = Regex::new(r"#ip (\d)").unwrap();
-    let instruct_regex = Regex::new(r"([a-z]+) (\d+) (\d+) (\d+)").unwrap();
-    let mut lines = input.lines();
-    let mut ip = usize::MAX;
-    let mut program: Vec<Instruction> = vec![];
-    while let Some(line) = lines.next() {
-        if ip_regex.is_match(line) {
-            // Line matching the instruction pointer regex will only match once
-            for capture in ip_regex.captures_iter(line) {
-                ip = capture[1].parse::<usize>().unwrap();
-                break;
-            }
-        } else {
-            for capture in instruct_regex.captures_iter(line) {
-                let op = Operation::from_string(&capture[1]).unwrap();
-                let val_a = capture[2].parse::<usize>().unwrap();
-                let val_b = capture[3].parse::<usize>().unwrap();
-                let val_c = capture[4].parse::<usize>().unwrap();
-                let values = (val_a, val_b, val_c);
-                let instruction = Instruction::new(op, values);
-                program.push(instruction);
-            }
-        }
-    }
-    return (ip, program);
+fn generate_input(input: &str) -> Program {
+    return input.parse::<Program>().unwrap();
 }
 
 #[aoc(day19, part1)]
-fn solve_part_1(input: &(usize, Vec<Instruction>)) -> usize {
-    let mut wrist_computer = WristComputer::new(Some(input.0));
-    wrist_computer.execute_program(&input.1);
+fn solve_part_1(input: &Program) -> usize {
+    let mut wrist_computer = WristComputer::new(input.get_ip_reg());
+    wrist_computer.enable_optimisation();
+    wrist_computer.execute_program(input.get_instructions());
     return wrist_computer.get_registers()[0];
 }
 
 #[aoc(day19, part2)]
-fn solve_part_2(_input: &(usize, Vec<Instruction>)) -> usize {
-    return test_wristcomp_background_process();
+fn solve_part_2(input: &Program) -> usize {
+    return calculate_background_process_result(input, 1);
 }
 
-/// Calculates the value in register 0 after the background process described in AoC 2018 D19, P2.
+/// Calculates the value in register 0 after the background process described in AoC 2018 D19
+/// is run with the given initial value in register 0.
 /// 
-/// Determined by observing execution of background process by wrist computer and reverse
-/// engineering the main loop. Some optimisations were added after the reverse engineering to get
-/// to the outcome significantly faster.
-fn test_wristcomp_background_process() -> usize {
-    // Initialise all registers to values present when process enters primary loop
-    let mut reg: Vec<usize> = vec![0, 1, 0, 1, 2, 10551364];
-    loop {
-        reg[1] = 1;
-        loop {
-            // This check is an optimisation!
-            if reg[5] % reg[3] != 0 {
-                break;
-            }
-            reg[2] = reg[3] * reg[1];
-            if reg[2] == reg[5] {
-                reg[0] += reg[3];
-                break;
-            }
-            reg[1] += 1;
-            if reg[1] > reg[5] {
-                break;
+/// The background process calculates a target number in its setup phase, then its main loop sums
+/// the divisors of the target number in a very slow way. The program is run until it first enters
+/// the main loop, where the target number is the largest value held in the registers.
+fn calculate_background_process_result(program: &Program, reg_zero: usize) -> usize {
+    // Main loop is the outermost loop found in the program
+    let cfg = ControlFlowGraph::new(program.get_instructions(), program.get_ip_reg());
+    let main_loop = cfg.find_natural_loops().into_iter().next().expect("Day19 - no loop found in program.");
+    let main_loop_start = cfg.get_block(main_loop.header).unwrap().start;
+    // Run setup phase of program
+    let mut wrist_computer = WristComputer::new(program.get_ip_reg());
+    wrist_computer.update_register_zero(reg_zero);
+    wrist_computer.add_breakpoint(main_loop_start);
+    if wrist_computer.run_until_breakpoint(program.get_instructions()) == StopReason::Halted {
+        panic!("Day19 - program halted before entering main loop.");
+    }
+    // Extract target number, ignoring the instruction pointer register
+    let target = wrist_computer.get_registers().iter().enumerate()
+        .filter(|(reg, _val)| Some(*reg) != program.get_ip_reg())
+        .map(|(_reg, val)| *val)
+        .max()
+        .unwrap();
+    return calculate_divisor_sum(target);
+}
+
+/// Calculates the sum of all divisors of the given number (including 1 and itself).
+fn calculate_divisor_sum(num: usize) -> usize {
+    let mut sum = 0;
+    let mut div = 1;
+    while div * div <= num {
+        if num % div == 0 {
+            sum += div;
+            if div * div != num {
+                sum += num / div;
             }
-        } 
-        reg[3] += 1;
-        if reg[3] > reg[5] {
-            break;
         }
+        div += 1;
     }
-    return reg[0];
+    return sum;
 }
 
 #[cfg(test)]
@@ -89,6 +75,25 @@ mod tests {
         assert_eq!(1694, result);
     }
 
+    #[test]
+    fn test_d19_optimised_matches_plain() {
+        let input = generate_input(&std::fs::read_to_string("./input/2018/day19.txt").unwrap());
+        let mut plain_comp = WristComputer::new(input.get_ip_reg());
+        plain_comp.execute_program(input.get_instructions());
+        let mut opt_comp = WristComputer::new(input.get_ip_reg());
+        opt_comp.enable_optimisation();
+        opt_comp.execute_program(input.get_instructions());
+        assert_eq!(plain_comp.get_registers(), opt_comp.get_registers());
+        assert!(opt_comp.get_steps() < plain_comp.get_steps());
+    }
+
+    #[test]
+    fn test_d19_p1_divisor_sum() {
+        let input = generate_input(&std::fs::read_to_string("./input/2018/day19.txt").unwrap());
+        let result = calculate_background_process_result(&input, 0);
+        assert_eq!(1694, result);
+    }
+
     #[test]
     fn test_d19_p2_proper() {
         let input = generate_input(&std::fs::read_to_string("./input/2018/day19.txt").unwrap());
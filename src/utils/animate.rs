@@ -0,0 +1,254 @@
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+
+/// ANSI escape sequences used to redraw frames in place.
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_TO_END: &str = "\x1b[J";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// Shortest frame delay that can be reached by speeding up an animation.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(1);
+
+const CONTROLS_HELP: &str = "[enter] play/pause  [s] step  [+/-] speed  [q] quit";
+
+/// Simulation that evolves in discrete ticks, such as carts moving around tracks.
+pub trait Simulation {
+    /// Advances the simulation by one tick.
+    fn tick(&mut self);
+
+    /// Checks if the simulation has finished, after which it is not ticked any further.
+    fn is_finished(&self) -> bool {
+        return false;
+    }
+}
+
+/// Controls for a running animation, entered one per line on stdin.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AnimControl {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Quit,
+}
+
+impl AnimControl {
+    /// Parses a line of user input into a control, returning None if it is not recognised.
+    pub fn from_input(input: &str) -> Option<AnimControl> {
+        match input.trim() {
+            "" | "p" => return Some(AnimControl::TogglePause),
+            "s" => return Some(AnimControl::Step),
+            "+" => return Some(AnimControl::Faster),
+            "-" => return Some(AnimControl::Slower),
+            "q" => return Some(AnimControl::Quit),
+            _ => return None,
+        }
+    }
+}
+
+/// Animates a simulation in the terminal, redrawing the rendered frame in place after each tick.
+pub struct Animator<W> {
+    output: W,
+    frame_delay: Duration,
+    start_paused: bool,
+    max_ticks: Option<u64>,
+}
+
+impl Animator<io::Stdout> {
+    /// Creates a new animator drawing to stdout with the given delay between frames.
+    pub fn new(frame_delay: Duration) -> Self {
+        return Animator::with_output(io::stdout(), frame_delay);
+    }
+
+    /// Runs the animation, reading controls from stdin until it is quit or the simulation
+    /// finishes. Returns the number of ticks conducted.
+    pub fn run<S, F>(&mut self, sim: &mut S, render: F) -> io::Result<u64> where S: Simulation, F: Fn(&S) -> String {
+        return self.run_with_controls(sim, render, spawn_stdin_controls());
+    }
+}
+
+impl<W: Write> Animator<W> {
+    /// Creates a new animator drawing to the given output with the given delay between frames.
+    pub fn with_output(output: W, frame_delay: Duration) -> Self {
+        Self {
+            output: output,
+            frame_delay: frame_delay,
+            start_paused: false,
+            max_ticks: None,
+        }
+    }
+
+    /// Sets whether the animation waits for the user to press play before the first tick.
+    pub fn with_start_paused(mut self, start_paused: bool) -> Self {
+        self.start_paused = start_paused;
+        return self;
+    }
+
+    /// Sets the number of ticks after which the animation stops, even if the simulation has not
+    /// finished.
+    pub fn with_max_ticks(mut self, max_ticks: u64) -> Self {
+        self.max_ticks = Some(max_ticks);
+        return self;
+    }
+
+    pub fn get_frame_delay(&self) -> Duration {
+        return self.frame_delay;
+    }
+
+    pub fn get_output(&self) -> &W {
+        return &self.output;
+    }
+
+    /// Runs the animation with controls received from the channel, until it is quit or the
+    /// simulation finishes. Returns the number of ticks conducted.
+    ///
+    /// If the channel is disconnected, a playing animation runs to the end and a paused one stops.
+    pub fn run_with_controls<S, F>(&mut self, sim: &mut S, render: F, controls: Receiver<AnimControl>) -> io::Result<u64>
+            where S: Simulation, F: Fn(&S) -> String {
+        let mut ticks = 0;
+        let mut paused = self.start_paused;
+        write!(self.output, "{}{}", CLEAR_SCREEN, HIDE_CURSOR)?;
+        loop {
+            let finished = sim.is_finished() || self.max_ticks.is_some_and(|max| ticks >= max);
+            self.draw_frame(&render(sim), ticks, paused, finished)?;
+            if finished {
+                break;
+            }
+            // Wait for the user while paused, otherwise until the next frame is due
+            let control = if paused {
+                match controls.recv() {
+                    Ok(control) => Some(control),
+                    Err(_) => break,
+                }
+            } else {
+                match controls.recv_timeout(self.frame_delay) {
+                    Ok(control) => Some(control),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(self.frame_delay);
+                        None
+                    },
+                }
+            };
+            match control {
+                None => {
+                    sim.tick();
+                    ticks += 1;
+                },
+                Some(AnimControl::TogglePause) => paused = !paused,
+                Some(AnimControl::Step) => {
+                    paused = true;
+                    sim.tick();
+                    ticks += 1;
+                },
+                Some(AnimControl::Faster) => self.frame_delay = (self.frame_delay / 2).max(MIN_FRAME_DELAY),
+                Some(AnimControl::Slower) => self.frame_delay *= 2,
+                Some(AnimControl::Quit) => break,
+            }
+        }
+        write!(self.output, "{}", SHOW_CURSOR)?;
+        self.output.flush()?;
+        return Ok(ticks);
+    }
+
+    /// Redraws the frame over the previous one, followed by a status line.
+    fn draw_frame(&mut self, frame: &str, ticks: u64, paused: bool, finished: bool) -> io::Result<()> {
+        let state = if finished { "finished" } else if paused { "paused" } else { "playing" };
+        write!(self.output, "{}{}", CURSOR_HOME, frame)?;
+        if !frame.is_empty() && !frame.ends_with('\n') {
+            writeln!(self.output)?;
+        }
+        writeln!(self.output, "tick {} ({}, {}ms/frame)  {}", ticks, state, self.frame_delay.as_millis(), CONTROLS_HELP)?;
+        write!(self.output, "{}", CLEAR_TO_END)?;
+        return self.output.flush();
+    }
+}
+
+/// Spawns a thread that reads controls from stdin, one per line. The channel is disconnected
+/// when stdin is closed.
+fn spawn_stdin_controls() -> Receiver<AnimControl> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => return,
+            };
+            if let Some(control) = AnimControl::from_input(&line) {
+                if sender.send(control).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    return receiver;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts up from zero, finishing at the limit.
+    struct Counter {
+        value: u64,
+        limit: u64,
+    }
+
+    impl Simulation for Counter {
+        fn tick(&mut self) {
+            self.value += 1;
+        }
+
+        fn is_finished(&self) -> bool {
+            return self.value >= self.limit;
+        }
+    }
+
+    fn render_counter(counter: &Counter) -> String {
+        return format!("value={}\n", counter.value);
+    }
+
+    #[test]
+    fn test_animator_plays_to_end() {
+        let mut counter = Counter { value: 0, limit: 3 };
+        let (_sender, receiver) = mpsc::channel();
+        let mut animator = Animator::with_output(Vec::<u8>::new(), Duration::from_millis(1));
+        assert_eq!(3, animator.run_with_controls(&mut counter, render_counter, receiver).unwrap());
+        let output = String::from_utf8(animator.get_output().clone()).unwrap();
+        assert!(output.starts_with(CLEAR_SCREEN));
+        assert!(output.ends_with(SHOW_CURSOR));
+        assert_eq!(4, output.matches(CURSOR_HOME).count());
+        assert!(output.contains("value=3\ntick 3 (finished"));
+    }
+
+    #[test]
+    fn test_animator_controls() {
+        let mut counter = Counter { value: 0, limit: 100 };
+        let (sender, receiver) = mpsc::channel();
+        for control in [AnimControl::Step, AnimControl::Step, AnimControl::Slower, AnimControl::Quit].iter() {
+            sender.send(*control).unwrap();
+        }
+        let mut animator = Animator::with_output(Vec::<u8>::new(), Duration::from_secs(60)).with_start_paused(true);
+        assert_eq!(2, animator.run_with_controls(&mut counter, render_counter, receiver).unwrap());
+        assert_eq!(Duration::from_secs(120), animator.get_frame_delay());
+        // Paused animation stops once no more controls can arrive
+        let (sender, receiver) = mpsc::channel();
+        sender.send(AnimControl::Step).unwrap();
+        drop(sender);
+        assert_eq!(1, animator.run_with_controls(&mut counter, render_counter, receiver).unwrap());
+        let mut animator = Animator::with_output(Vec::<u8>::new(), Duration::from_millis(1)).with_max_ticks(5);
+        assert_eq!(5, animator.run_with_controls(&mut counter, render_counter, mpsc::channel().1).unwrap());
+        assert_eq!(8, counter.value);
+        assert_eq!(Some(AnimControl::TogglePause), AnimControl::from_input(""));
+        assert_eq!(None, AnimControl::from_input("x"));
+    }
+}
@@ -1,4 +1,7 @@
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+use enum_iterator::IntoEnumIterator;
+
+/// One of the four cardinal directions, with North facing towards decreasing y-coordinates.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, IntoEnumIterator)]
 pub enum Direction {
     North,
     South,
@@ -52,7 +55,7 @@ impl Direction {
         }
     }
 
-    /// Gets the direction resulting from rotating 90 degrees CLOCK-WISE.
+    /// Gets the direction resulting from rotating 90 degrees COUNTER-CLOCK-WISE.
     pub fn get_ccw_rotate(&self) -> Direction {
         match self {
             Direction::North => {
@@ -69,4 +72,179 @@ impl Direction {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Gets the direction facing the opposite way.
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => return Direction::South,
+            Direction::South => return Direction::North,
+            Direction::East => return Direction::West,
+            Direction::West => return Direction::East,
+        }
+    }
+
+    /// Gets the direction faced after turning 90 degrees to the left.
+    pub fn turn_left(&self) -> Direction {
+        return self.get_ccw_rotate();
+    }
+
+    /// Gets the direction faced after turning 90 degrees to the right.
+    pub fn turn_right(&self) -> Direction {
+        return self.get_cw_rotate();
+    }
+
+    /// Gets the direction faced after turning 180 degrees.
+    pub fn turn_around(&self) -> Direction {
+        return self.opposite();
+    }
+
+    /// Gets the change in x- and y-coordinates from moving one step in the direction.
+    pub fn get_delta(&self) -> (i64, i64) {
+        match self {
+            Direction::North => return (0, -1),
+            Direction::South => return (0, 1),
+            Direction::East => return (1, 0),
+            Direction::West => return (-1, 0),
+        }
+    }
+
+    /// Converts an arrow character ('^', 'v', '>' or '<') into the direction it points.
+    pub fn from_arrow(c: char) -> Option<Direction> {
+        match c {
+            '^' => return Some(Direction::North),
+            'v' => return Some(Direction::South),
+            '>' => return Some(Direction::East),
+            '<' => return Some(Direction::West),
+            _ => return None,
+        }
+    }
+
+    /// Gets the arrow character ('^', 'v', '>' or '<') pointing in the direction.
+    pub fn to_arrow(&self) -> char {
+        match self {
+            Direction::North => return '^',
+            Direction::South => return 'v',
+            Direction::East => return '>',
+            Direction::West => return '<',
+        }
+    }
+
+    /// Converts a compass character ('N', 'S', 'E' or 'W') into its direction.
+    pub fn from_compass(c: char) -> Option<Direction> {
+        match c {
+            'N' => return Some(Direction::North),
+            'S' => return Some(Direction::South),
+            'E' => return Some(Direction::East),
+            'W' => return Some(Direction::West),
+            _ => return None,
+        }
+    }
+
+    /// Gets the compass character ('N', 'S', 'E' or 'W') for the direction.
+    pub fn to_compass(&self) -> char {
+        match self {
+            Direction::North => return 'N',
+            Direction::South => return 'S',
+            Direction::East => return 'E',
+            Direction::West => return 'W',
+        }
+    }
+}
+
+/// One of the four cardinal or four diagonal directions, declared in clock-wise order starting
+/// from North.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, IntoEnumIterator)]
+pub enum Direction8 {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest
+}
+
+impl Direction8 {
+    /// Gets the direction facing the opposite way.
+    pub fn opposite(&self) -> Direction8 {
+        return self.rotate_eighths(4);
+    }
+
+    /// Gets the direction faced after turning 45 degrees to the left.
+    pub fn turn_left(&self) -> Direction8 {
+        return self.rotate_eighths(7);
+    }
+
+    /// Gets the direction faced after turning 45 degrees to the right.
+    pub fn turn_right(&self) -> Direction8 {
+        return self.rotate_eighths(1);
+    }
+
+    /// Gets the direction faced after turning 180 degrees.
+    pub fn turn_around(&self) -> Direction8 {
+        return self.opposite();
+    }
+
+    /// Checks if the direction is one of the four diagonals.
+    pub fn is_diagonal(&self) -> bool {
+        let (delta_x, delta_y) = self.get_delta();
+        return delta_x != 0 && delta_y != 0;
+    }
+
+    /// Gets the change in x- and y-coordinates from moving one step in the direction.
+    pub fn get_delta(&self) -> (i64, i64) {
+        match self {
+            Direction8::North => return (0, -1),
+            Direction8::NorthEast => return (1, -1),
+            Direction8::East => return (1, 0),
+            Direction8::SouthEast => return (1, 1),
+            Direction8::South => return (0, 1),
+            Direction8::SouthWest => return (-1, 1),
+            Direction8::West => return (-1, 0),
+            Direction8::NorthWest => return (-1, -1),
+        }
+    }
+
+    /// Gets the direction resulting from rotating clock-wise by the given number of 45 degree
+    /// turns.
+    fn rotate_eighths(&self, turns: usize) -> Direction8 {
+        let index = Direction8::into_enum_iter().position(|dir| dir == *self).unwrap();
+        return Direction8::into_enum_iter().nth((index + turns) % 8).unwrap();
+    }
+}
+
+impl From<Direction> for Direction8 {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::North => return Direction8::North,
+            Direction::South => return Direction8::South,
+            Direction::East => return Direction8::East,
+            Direction::West => return Direction8::West,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction_turns() {
+        for dir in Direction::into_enum_iter() {
+            assert_eq!(dir, dir.turn_left().turn_right());
+            assert_eq!(dir.turn_around(), dir.turn_right().turn_right());
+            assert!(dir.is_opposite(dir.opposite()));
+            assert_eq!(Some(dir), Direction::from_arrow(dir.to_arrow()));
+            assert_eq!(Some(dir), Direction::from_compass(dir.to_compass()));
+            let dir8 = Direction8::from(dir);
+            assert_eq!(dir.get_delta(), dir8.get_delta());
+            assert_eq!(Direction8::from(dir.turn_right()), dir8.turn_right().turn_right());
+        }
+        assert_eq!(4, Direction::into_enum_iter().count());
+        assert_eq!(None, Direction::from_arrow('x'));
+        assert_eq!(Direction8::SouthWest, Direction8::NorthEast.opposite());
+        assert_eq!(Direction8::NorthWest, Direction8::North.turn_left());
+        assert_eq!(4, Direction8::into_enum_iter().filter(|dir| dir.is_diagonal()).count());
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use super::parse::MapParseError;
+use super::parse::parse_grid_with;
+use super::point2d::Point2D;
+
+/// Dense rectangular map of tiles stored in a flat Vec in reading order (row by row).
+///
+/// The top-left tile of the grid is located at the origin, so grids can cover any rectangular
+/// region rather than only those starting at (0, 0).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    origin: Point2D,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Creates a new grid with its top-left tile at (0, 0) and all tiles set to the given value.
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        return Grid::with_origin(Point2D::new(0, 0), width, height, fill);
+    }
+
+    /// Creates a new grid with its top-left tile at the origin and all tiles set to the given value.
+    pub fn with_origin(origin: Point2D, width: usize, height: usize, fill: T) -> Self {
+        Self {
+            cells: vec![fill; width * height],
+            width: width,
+            height: height,
+            origin: origin,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Creates a new grid with its top-left tile at the origin, with each tile set to the value
+    /// returned by the function for its location.
+    pub fn from_fn<F>(origin: Point2D, width: usize, height: usize, mut f: F) -> Self where F: FnMut(Point2D) -> T {
+        let mut cells = Vec::<T>::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(origin.move_point(x as i64, y as i64)));
+            }
+        }
+        Self {
+            cells: cells,
+            width: width,
+            height: height,
+            origin: origin,
+        }
+    }
+
+    /// Parses a grid from ASCII art with one row per line, converting each character to a tile
+    /// with the given function (which returns None if the character is invalid). The top-left
+    /// character is located at (0, 0).
+    ///
+    /// Returns an error if a character is invalid or the lines are not all the same length.
+    pub fn from_ascii<F>(input: &str, f: F) -> Result<Self, MapParseError> where F: FnMut(char) -> Option<T> {
+        return parse_grid_with(input, f);
+    }
+
+    /// Creates a new grid with its top-left tile at (0, 0) from tiles given in reading order.
+    pub(super) fn from_cells(cells: Vec<T>, width: usize, height: usize) -> Self {
+        assert_eq!(cells.len(), width * height, "Grid - cell count does not match dimensions.");
+        Self {
+            cells: cells,
+            width: width,
+            height: height,
+            origin: Point2D::new(0, 0),
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> usize {
+        return self.height;
+    }
+
+    /// Gets the location of the top-left tile of the grid.
+    pub fn get_origin(&self) -> Point2D {
+        return self.origin;
+    }
+
+    /// Gets the location of the bottom-right tile of the grid.
+    pub fn get_bottom_right(&self) -> Point2D {
+        return self.origin.move_point(self.width as i64 - 1, self.height as i64 - 1);
+    }
+
+    /// Checks if the location is within the bounds of the grid.
+    pub fn contains(&self, loc: &Point2D) -> bool {
+        return self.get_index(loc).is_some();
+    }
+
+    pub fn get(&self, loc: &Point2D) -> Option<&T> {
+        return self.get_index(loc).map(|i| &self.cells[i]);
+    }
+
+    pub fn get_mut(&mut self, loc: &Point2D) -> Option<&mut T> {
+        match self.get_index(loc) {
+            Some(i) => return Some(&mut self.cells[i]),
+            None => return None,
+        }
+    }
+
+    /// Sets the tile at the location, returning false (and leaving the grid unchanged) if the
+    /// location is outside of the grid.
+    pub fn set(&mut self, loc: &Point2D, val: T) -> bool {
+        match self.get_mut(loc) {
+            Some(tile) => {
+                *tile = val;
+                return true;
+            },
+            None => return false,
+        }
+    }
+
+    /// Iterates over the locations of all tiles in reading order.
+    pub fn points(&self) -> impl Iterator<Item = Point2D> {
+        let (origin, width) = (self.origin, self.width);
+        return (0..self.cells.len()).map(move |i| origin.move_point((i % width) as i64, (i / width) as i64));
+    }
+
+    /// Iterates over all tiles along with their locations, in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2D, &T)> {
+        return self.points().zip(self.cells.iter());
+    }
+
+    /// Iterates over all tiles in reading order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        return self.cells.iter();
+    }
+
+    /// Iterates over the rows of the grid from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // Chunk size must be non-zero, even for an empty grid
+        return self.cells.chunks(self.width.max(1));
+    }
+
+    /// Iterates over the tiles in the row with the given y-coordinate from left to right. The
+    /// iterator is empty if the row is outside of the grid.
+    pub fn row(&self, pos_y: i64) -> impl Iterator<Item = &T> {
+        let y = pos_y - self.origin.pos_y;
+        let range = if y >= 0 && (y as usize) < self.height {
+            let start = y as usize * self.width;
+            start..(start + self.width)
+        } else {
+            0..0
+        };
+        return self.cells[range].iter();
+    }
+
+    /// Iterates over the tiles in the column with the given x-coordinate from top to bottom. The
+    /// iterator is empty if the column is outside of the grid.
+    pub fn column(&self, pos_x: i64) -> impl Iterator<Item = &T> {
+        let x = pos_x - self.origin.pos_x;
+        let (start, height) = if x >= 0 && (x as usize) < self.width { (x as usize, self.height) } else { (0, 0) };
+        return self.cells.iter().skip(start).step_by(self.width.max(1)).take(height);
+    }
+
+    /// Iterates over the tiles up, down, left and right of the location that are within the grid,
+    /// in reading order.
+    pub fn adjacent(&self, loc: &Point2D) -> impl Iterator<Item = (Point2D, &T)> {
+        let points = [loc.move_point(0, -1), loc.move_point(-1, 0), loc.move_point(1, 0), loc.move_point(0, 1)];
+        return IntoIterator::into_iter(points).filter_map(move |p| self.get(&p).map(|tile| (p, tile)));
+    }
+
+    /// Iterates over the (up to eight) tiles surrounding the location that are within the grid,
+    /// including diagonals, in reading order.
+    pub fn surrounding(&self, loc: &Point2D) -> impl Iterator<Item = (Point2D, &T)> {
+        let loc = *loc;
+        return (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| *dx != 0 || *dy != 0)
+            .map(move |(dx, dy)| loc.move_point(dx, dy))
+            .filter_map(move |p| self.get(&p).map(|tile| (p, tile)));
+    }
+
+    /// Creates a new grid covering the same locations, with each tile converted by the function.
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U> where F: FnMut(Point2D, &T) -> U {
+        return Grid {
+            cells: self.iter().map(|(loc, tile)| f(loc, tile)).collect(),
+            width: self.width,
+            height: self.height,
+            origin: self.origin,
+        };
+    }
+
+    /// Gets the index into the cells of the tile at the location, if it is within the grid.
+    fn get_index(&self, loc: &Point2D) -> Option<usize> {
+        let x = loc.pos_x.checked_sub(self.origin.pos_x)?;
+        let y = loc.pos_y.checked_sub(self.origin.pos_y)?;
+        if x < 0 || y < 0 || x as u64 >= self.width as u64 || y as u64 >= self.height as u64 {
+            return None;
+        }
+        return Some(y as usize * self.width + x as usize);
+    }
+}
+
+impl<T> Index<Point2D> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, loc: Point2D) -> &T {
+        match self.get(&loc) {
+            Some(tile) => return tile,
+            None => panic!("Grid - location {} out of bounds.", loc),
+        }
+    }
+}
+
+impl<T> IndexMut<Point2D> for Grid<T> {
+    fn index_mut(&mut self, loc: Point2D) -> &mut T {
+        match self.get_index(&loc) {
+            Some(i) => return &mut self.cells[i],
+            None => panic!("Grid - location {} out of bounds.", loc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse::MapParseErrorKind;
+
+    #[test]
+    fn test_grid_access() {
+        let mut grid = Grid::from_fn(Point2D::new(1, 1), 3, 2, |p| p.pos_x * 10 + p.pos_y);
+        assert_eq!(Some(&11), grid.get(&Point2D::new(1, 1)));
+        assert_eq!(Some(&32), grid.get(&Point2D::new(3, 2)));
+        assert_eq!(None, grid.get(&Point2D::new(0, 1)));
+        assert_eq!(None, grid.get(&Point2D::new(4, 1)));
+        assert!(grid.set(&Point2D::new(2, 2), 0));
+        assert!(!grid.set(&Point2D::new(2, 3), 0));
+        assert_eq!(vec![11, 21, 31], grid.row(1).copied().collect::<Vec<i64>>());
+        assert_eq!(vec![21, 0], grid.column(2).copied().collect::<Vec<i64>>());
+        assert_eq!(0, grid.column(7).count());
+        assert_eq!(Point2D::new(3, 2), grid.get_bottom_right());
+        grid[Point2D::new(1, 2)] = 5;
+        assert_eq!(vec![&[11, 21, 31][..], &[5, 0, 32][..]], grid.rows().collect::<Vec<&[i64]>>());
+    }
+
+    #[test]
+    fn test_grid_neighbours() {
+        let grid = Grid::from_ascii("abc\ndef\nghi", Some).unwrap();
+        assert_eq!(3, grid.get_width());
+        assert_eq!(3, grid.get_height());
+        let centre = Point2D::new(1, 1);
+        assert_eq!("bdfh", grid.adjacent(&centre).map(|(_, c)| *c).collect::<String>());
+        assert_eq!("abcdfghi", grid.surrounding(&centre).map(|(_, c)| *c).collect::<String>());
+        let corner = Point2D::new(0, 0);
+        assert_eq!(vec![Point2D::new(1, 0), Point2D::new(0, 1)], grid.adjacent(&corner).map(|(p, _)| p).collect::<Vec<Point2D>>());
+        assert_eq!("bde", grid.surrounding(&corner).map(|(_, c)| *c).collect::<String>());
+    }
+
+    #[test]
+    fn test_grid_jagged_lines() {
+        let err = Grid::from_ascii("abc\nde", Some).unwrap_err();
+        assert_eq!(MapParseError::new(2, 3, MapParseErrorKind::JaggedLine { expected: 3, found: 2 }), err);
+        let err = Grid::from_ascii("abc\nd-f", |c| if c.is_alphabetic() { Some(c) } else { None }).unwrap_err();
+        assert_eq!(MapParseError::new(2, 2, MapParseErrorKind::InvalidCharacter('-')), err);
+    }
+}
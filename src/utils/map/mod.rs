@@ -1,5 +1,23 @@
-mod point2d;
 mod direction;
+mod grid;
+mod parse;
+mod point2d;
+mod render;
 
-pub use self::point2d::Point2D;
 pub use self::direction::Direction;
+pub use self::direction::Direction8;
+pub use self::grid::Grid;
+pub use self::parse::MapEntity;
+pub use self::parse::MapParseError;
+pub use self::parse::MapParseErrorKind;
+pub use self::parse::ParsedMap;
+pub use self::parse::parse_grid;
+pub use self::parse::parse_grid_padded;
+pub use self::parse::parse_grid_with;
+pub use self::parse::parse_map;
+pub use self::parse::parse_map_padded;
+pub use self::point2d::Point2D;
+pub use self::render::MapRenderer;
+pub use self::render::RenderTile;
+pub use self::render::Rgb;
+pub use self::render::TileMap;
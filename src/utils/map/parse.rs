@@ -0,0 +1,221 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use super::grid::Grid;
+use super::point2d::Point2D;
+
+/// Entity that can be shown on an ASCII map in place of the tile it is standing on, such as a
+/// combat unit or crop cart.
+pub trait MapEntity: TryFrom<char> {
+    type Tile;
+
+    /// Gets the tile hidden underneath the entity on the map.
+    fn get_tile_beneath(&self) -> Self::Tile;
+}
+
+/// Kinds of errors that can occur when parsing an ASCII map.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MapParseErrorKind {
+    InvalidCharacter(char),
+    JaggedLine { expected: usize, found: usize },
+}
+
+/// Error raised when parsing an ASCII map, located by 1-indexed row and column.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MapParseError {
+    pub row: usize,
+    pub column: usize,
+    pub kind: MapParseErrorKind,
+}
+
+impl MapParseError {
+    pub fn new(row: usize, column: usize, kind: MapParseErrorKind) -> Self {
+        Self {
+            row: row,
+            column: column,
+            kind: kind,
+        }
+    }
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "row {}, column {}: ", self.row, self.column)?;
+        match self.kind {
+            MapParseErrorKind::InvalidCharacter(c) => return write!(f, "invalid map character {:?}", c),
+            MapParseErrorKind::JaggedLine { expected, found } => {
+                return write!(f, "line has {} characters, expected {}", found, expected);
+            }
+        }
+    }
+}
+
+impl Error for MapParseError {}
+
+/// Grid of tiles parsed from an ASCII map along with the located entities shown on it.
+pub type ParsedMap<T, E> = (Grid<T>, Vec<(Point2D, E)>);
+
+/// Parses a rectangular ASCII map with one row per line into a grid of tiles, with the top-left
+/// character located at (0, 0).
+pub fn parse_grid<T>(input: &str) -> Result<Grid<T>, MapParseError> where T: TryFrom<char> {
+    return parse_lines(input, None, |_loc, c| T::try_from(c).ok());
+}
+
+/// Parses a rectangular ASCII map with one row per line into a grid of tiles, converting each
+/// character to a tile with the given function, which returns None if the character is invalid.
+pub fn parse_grid_with<T, F>(input: &str, mut f: F) -> Result<Grid<T>, MapParseError> where F: FnMut(char) -> Option<T> {
+    return parse_lines(input, None, |_loc, c| f(c));
+}
+
+/// Parses an ASCII map with one row per line into a grid of tiles, as with parse_grid, except that
+/// lines shorter than the longest line are padded out with the fill tile.
+pub fn parse_grid_padded<T>(input: &str, fill: T) -> Result<Grid<T>, MapParseError> where T: TryFrom<char> + Clone {
+    return parse_lines(input, Some(&|| fill.clone()), |_loc, c| T::try_from(c).ok());
+}
+
+/// Parses a rectangular ASCII map with one row per line into a grid of tiles, along with the
+/// entities shown on the map (in reading order). Characters are checked for entities before tiles.
+pub fn parse_map<T, E>(input: &str) -> Result<ParsedMap<T, E>, MapParseError>
+        where T: TryFrom<char>, E: MapEntity<Tile = T> {
+    return parse_entities(input, None);
+}
+
+/// Parses an ASCII map with one row per line into a grid of tiles along with the entities shown on
+/// it, as with parse_map, except that lines shorter than the longest line are padded out with the
+/// fill tile.
+pub fn parse_map_padded<T, E>(input: &str, fill: T) -> Result<ParsedMap<T, E>, MapParseError>
+        where T: TryFrom<char> + Clone, E: MapEntity<Tile = T> {
+    return parse_entities(input, Some(&|| fill.clone()));
+}
+
+/// Parses the tiles and entities of the map, padding short lines with tiles from the fill function
+/// if one is given.
+fn parse_entities<T, E>(input: &str, fill: Option<&dyn Fn() -> T>) -> Result<ParsedMap<T, E>, MapParseError>
+        where T: TryFrom<char>, E: MapEntity<Tile = T> {
+    let mut entities = Vec::<(Point2D, E)>::new();
+    let grid = parse_lines(input, fill, |loc, c| {
+        if let Ok(entity) = E::try_from(c) {
+            let tile = entity.get_tile_beneath();
+            entities.push((loc, entity));
+            return Some(tile);
+        }
+        return T::try_from(c).ok();
+    })?;
+    return Ok((grid, entities));
+}
+
+/// Parses each character of the map into a tile using the function, which returns None if the
+/// character is invalid. If a fill function is given, lines shorter than the longest line are
+/// padded out with its tiles, otherwise they are an error.
+fn parse_lines<T, F>(input: &str, fill: Option<&dyn Fn() -> T>, mut f: F) -> Result<Grid<T>, MapParseError>
+        where F: FnMut(Point2D, char) -> Option<T> {
+    let padded_width = fill.map(|_| input.lines().map(|line| line.chars().count()).max().unwrap_or(0));
+    let mut cells = Vec::<T>::new();
+    let mut width: Option<usize> = None;
+    let mut height = 0;
+    for (y, line) in input.lines().enumerate() {
+        let mut row_width = 0;
+        for (x, c) in line.chars().enumerate() {
+            match f(Point2D::new(x as i64, y as i64), c) {
+                Some(tile) => cells.push(tile),
+                None => return Err(MapParseError::new(y + 1, x + 1, MapParseErrorKind::InvalidCharacter(c))),
+            }
+            row_width += 1;
+        }
+        if let (Some(fill), Some(padded_width)) = (fill, padded_width) {
+            cells.extend((row_width..padded_width).map(|_| fill()));
+            row_width = padded_width;
+        }
+        if let Some(expected) = width {
+            if row_width != expected {
+                let kind = MapParseErrorKind::JaggedLine { expected: expected, found: row_width };
+                return Err(MapParseError::new(y + 1, row_width.min(expected) + 1, kind));
+            }
+        }
+        width = Some(row_width);
+        height += 1;
+    }
+    return Ok(Grid::from_cells(cells, width.unwrap_or(0), height));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    enum Tile {
+        Wall,
+        Floor,
+    }
+
+    impl TryFrom<char> for Tile {
+        type Error = ();
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            match c {
+                '#' => return Ok(Tile::Wall),
+                '.' => return Ok(Tile::Floor),
+                _ => return Err(()),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    struct Unit(char);
+
+    impl TryFrom<char> for Unit {
+        type Error = ();
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            if c.is_ascii_uppercase() {
+                return Ok(Unit(c));
+            }
+            return Err(());
+        }
+    }
+
+    impl MapEntity for Unit {
+        type Tile = Tile;
+
+        fn get_tile_beneath(&self) -> Tile {
+            return Tile::Floor;
+        }
+    }
+
+    #[test]
+    fn test_parse_map() {
+        let (grid, units) = parse_map::<Tile, Unit>("####\n#A.#\n#.B#\n####").unwrap();
+        assert_eq!(4, grid.get_width());
+        assert_eq!(4, grid.get_height());
+        assert_eq!(Tile::Floor, grid[Point2D::new(1, 1)]);
+        assert_eq!(Tile::Wall, grid[Point2D::new(3, 2)]);
+        assert_eq!(vec![(Point2D::new(1, 1), Unit('A')), (Point2D::new(2, 2), Unit('B'))], units);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_grid::<Tile>("###\n#.x\n###").unwrap_err();
+        assert_eq!(MapParseError::new(2, 3, MapParseErrorKind::InvalidCharacter('x')), err);
+        assert_eq!("row 2, column 3: invalid map character 'x'", err.to_string());
+        let err = parse_grid::<Tile>("###\n#.\n###").unwrap_err();
+        assert_eq!(MapParseError::new(2, 3, MapParseErrorKind::JaggedLine { expected: 3, found: 2 }), err);
+        let err = parse_map::<Tile, Unit>("###\n#A#\n#.##").unwrap_err();
+        assert_eq!(MapParseError::new(3, 4, MapParseErrorKind::JaggedLine { expected: 3, found: 4 }), err);
+    }
+
+    #[test]
+    fn test_parse_padded() {
+        let grid = parse_grid_padded("##\n#.#\n#", Tile::Floor).unwrap();
+        assert_eq!(3, grid.get_width());
+        assert_eq!(3, grid.get_height());
+        assert_eq!(vec![Tile::Wall, Tile::Wall, Tile::Floor], grid.row(0).copied().collect::<Vec<Tile>>());
+        assert_eq!(vec![Tile::Wall, Tile::Floor, Tile::Floor], grid.row(2).copied().collect::<Vec<Tile>>());
+        let (grid, units) = parse_map_padded::<Tile, Unit>("#A\n#.B#", Tile::Wall).unwrap();
+        assert_eq!(4, grid.get_width());
+        assert_eq!(Tile::Wall, grid[Point2D::new(3, 0)]);
+        assert_eq!(vec![(Point2D::new(1, 0), Unit('A')), (Point2D::new(2, 1), Unit('B'))], units);
+        let err = parse_grid_padded("##\n#x", Tile::Floor).unwrap_err();
+        assert_eq!(MapParseError::new(2, 2, MapParseErrorKind::InvalidCharacter('x')), err);
+    }
+}
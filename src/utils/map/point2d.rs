@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 use std::fmt;
 
+use super::direction::Direction8;
+
 /// A simple struct used to represent a 2-dimensional point in Euclidian space.
 #[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
 pub struct Point2D {
@@ -24,6 +26,19 @@ impl Point2D {
         }
     }
 
+    /// Calculates the new point moved one step in the given direction, which can be a Direction or
+    /// Direction8.
+    pub fn step<D: Into<Direction8>>(&self, dir: D) -> Point2D {
+        return self.step_n(dir, 1);
+    }
+
+    /// Calculates the new point moved the given number of steps in the given direction. A negative
+    /// number of steps moves in the opposite direction.
+    pub fn step_n<D: Into<Direction8>>(&self, dir: D, steps: i64) -> Point2D {
+        let (delta_x, delta_y) = dir.into().get_delta();
+        return self.move_point(delta_x * steps, delta_y * steps);
+    }
+
     /// Calculates the Manhattan distance between current point and given point.
     pub fn calculate_manhattan_dist(&self, other: &Point2D) -> u64 {
         // Calculate distance in x- and y-axes
@@ -106,3 +121,19 @@ impl PartialOrd for Point2D {
         return Some(self.cmp(other));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::direction::Direction;
+
+    #[test]
+    fn test_point2d_step() {
+        let point = Point2D::new(3, 4);
+        assert_eq!(Point2D::new(3, 3), point.step(Direction::North));
+        assert_eq!(Point2D::new(0, 4), point.step_n(Direction::West, 3));
+        assert_eq!(Point2D::new(5, 4), point.step_n(Direction::West, -2));
+        assert_eq!(Point2D::new(4, 5), point.step(Direction8::SouthEast));
+        assert_eq!(Point2D::new(1, 2), point.step_n(Direction8::NorthWest, 2));
+    }
+}
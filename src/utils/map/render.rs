@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use super::grid::Grid;
+use super::point2d::Point2D;
+
+/// Colour of a single pixel in a rendered image.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self {
+            red: red,
+            green: green,
+            blue: blue,
+        }
+    }
+
+    /// Gets the perceived brightness of the colour, using the ITU-R BT.601 luma weights.
+    pub fn get_grey(&self) -> u8 {
+        let luma = 299 * self.red as u32 + 587 * self.green as u32 + 114 * self.blue as u32;
+        return ((luma + 500) / 1000) as u8;
+    }
+}
+
+/// Tile that can be drawn as a character or a coloured pixel when rendering a map.
+pub trait RenderTile {
+    fn get_char(&self) -> char;
+
+    fn get_colour(&self) -> Rgb;
+
+    /// Gets the character drawn at locations within the map bounds that have no tile.
+    fn get_empty_char() -> char where Self: Sized {
+        return '.';
+    }
+
+    /// Gets the colour drawn at locations within the map bounds that have no tile.
+    fn get_empty_colour() -> Rgb where Self: Sized {
+        return Rgb::BLACK;
+    }
+}
+
+/// Map of tiles keyed by location, which may be sparse.
+pub trait TileMap {
+    type Tile;
+
+    /// Gets the top-left and bottom-right corners of the smallest rectangle containing all tiles,
+    /// or None if the map is empty.
+    fn get_bounds(&self) -> Option<(Point2D, Point2D)>;
+
+    fn get_tile(&self, loc: &Point2D) -> Option<&Self::Tile>;
+}
+
+impl<T> TileMap for Grid<T> {
+    type Tile = T;
+
+    fn get_bounds(&self) -> Option<(Point2D, Point2D)> {
+        if self.get_width() == 0 || self.get_height() == 0 {
+            return None;
+        }
+        return Some((self.get_origin(), self.get_bottom_right()));
+    }
+
+    fn get_tile(&self, loc: &Point2D) -> Option<&T> {
+        return self.get(loc);
+    }
+}
+
+impl<T> TileMap for HashMap<Point2D, T> {
+    type Tile = T;
+
+    fn get_bounds(&self) -> Option<(Point2D, Point2D)> {
+        let min_x = self.keys().map(|loc| loc.pos_x).min()?;
+        let max_x = self.keys().map(|loc| loc.pos_x).max()?;
+        let min_y = self.keys().map(|loc| loc.pos_y).min()?;
+        let max_y = self.keys().map(|loc| loc.pos_y).max()?;
+        return Some((Point2D::new(min_x, min_y), Point2D::new(max_x, max_y)));
+    }
+
+    fn get_tile(&self, loc: &Point2D) -> Option<&T> {
+        return self.get(loc);
+    }
+}
+
+/// Renders a rectangular region of a map as text, or as a binary PPM (colour) or PGM (greyscale)
+/// image.
+///
+/// Markers can be drawn over the top of the map, for things that are not tiles (such as units or
+/// the source of a flow).
+pub struct MapRenderer<'a, M> {
+    map: &'a M,
+    bounds: Option<(Point2D, Point2D)>,
+    markers: HashMap<Point2D, (char, Rgb)>,
+    scale: usize,
+}
+
+impl<'a, M> MapRenderer<'a, M> where M: TileMap, M::Tile: RenderTile {
+    /// Creates a new renderer covering all tiles in the map, with each tile drawn as one pixel.
+    pub fn new(map: &'a M) -> Self {
+        Self {
+            map: map,
+            bounds: map.get_bounds(),
+            markers: HashMap::new(),
+            scale: 1,
+        }
+    }
+
+    /// Sets the (inclusive) top-left and bottom-right corners of the region to render.
+    pub fn with_bounds(mut self, top_left: Point2D, bottom_right: Point2D) -> Self {
+        self.bounds = Some((top_left, bottom_right));
+        return self;
+    }
+
+    /// Sets the width and height in pixels of each tile in rendered images.
+    ///
+    /// Panics if the scale is 0.
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        if scale == 0 {
+            panic!("MapRenderer - scale must be non-zero.");
+        }
+        self.scale = scale;
+        return self;
+    }
+
+    /// Draws the marker at the location instead of any tile present there.
+    pub fn with_marker(mut self, loc: Point2D, c: char, colour: Rgb) -> Self {
+        self.markers.insert(loc, (c, colour));
+        return self;
+    }
+
+    /// Gets the width and height in tiles of the rendered region.
+    pub fn get_size(&self) -> (usize, usize) {
+        match self.bounds {
+            Some((top_left, bottom_right)) => {
+                let width = (bottom_right.pos_x - top_left.pos_x + 1).max(0) as usize;
+                let height = (bottom_right.pos_y - top_left.pos_y + 1).max(0) as usize;
+                return (width, height);
+            },
+            None => return (0, 0),
+        }
+    }
+
+    /// Renders the region as text, with each row terminated by a newline.
+    pub fn render_string(&self) -> String {
+        let (width, height) = self.get_size();
+        let mut output = String::with_capacity((width + 1) * height);
+        for row in self.rows() {
+            for loc in row {
+                output.push(self.get_char(&loc));
+            }
+            output.push('\n');
+        }
+        return output;
+    }
+
+    /// Writes the region as a binary PPM (P6) image.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (width, height) = self.get_size();
+        write!(writer, "P6\n{} {}\n255\n", width * self.scale, height * self.scale)?;
+        return self.write_pixels(writer, |colour| vec![colour.red, colour.green, colour.blue]);
+    }
+
+    /// Writes the region as a binary PGM (P5) image, converting tile colours to greyscale.
+    pub fn write_pgm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (width, height) = self.get_size();
+        write!(writer, "P5\n{} {}\n255\n", width * self.scale, height * self.scale)?;
+        return self.write_pixels(writer, |colour| vec![colour.get_grey()]);
+    }
+
+    /// Saves the region as a binary PPM image at the given path, replacing any existing file.
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut writer)?;
+        return writer.flush();
+    }
+
+    /// Saves the region as a binary PGM image at the given path, replacing any existing file.
+    pub fn save_pgm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_pgm(&mut writer)?;
+        return writer.flush();
+    }
+
+    /// Writes the pixel data for each row of the region, repeating each tile to match the scale.
+    fn write_pixels<W, F>(&self, writer: &mut W, f: F) -> io::Result<()> where W: Write, F: Fn(Rgb) -> Vec<u8> {
+        for row in self.rows() {
+            let mut line = Vec::<u8>::new();
+            for loc in row {
+                let pixel = f(self.get_colour(&loc));
+                for _ in 0..self.scale {
+                    line.extend_from_slice(&pixel);
+                }
+            }
+            for _ in 0..self.scale {
+                writer.write_all(&line)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Iterates over the rows of locations within the region, from top to bottom.
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Point2D>> {
+        let (width, height) = self.get_size();
+        let top_left = self.bounds.map(|(top_left, _)| top_left).unwrap_or_else(|| Point2D::new(0, 0));
+        return (0..height as i64).map(move |y| (0..width as i64).map(move |x| top_left.move_point(x, y)));
+    }
+
+    fn get_char(&self, loc: &Point2D) -> char {
+        if let Some((c, _)) = self.markers.get(loc) {
+            return *c;
+        }
+        match self.map.get_tile(loc) {
+            Some(tile) => return tile.get_char(),
+            None => return M::Tile::get_empty_char(),
+        }
+    }
+
+    fn get_colour(&self, loc: &Point2D) -> Rgb {
+        if let Some((_, colour)) = self.markers.get(loc) {
+            return *colour;
+        }
+        match self.map.get_tile(loc) {
+            Some(tile) => return tile.get_colour(),
+            None => return M::Tile::get_empty_colour(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    enum Tile {
+        Wall,
+        Water,
+    }
+
+    impl RenderTile for Tile {
+        fn get_char(&self) -> char {
+            match self {
+                Tile::Wall => return '#',
+                Tile::Water => return '~',
+            }
+        }
+
+        fn get_colour(&self) -> Rgb {
+            match self {
+                Tile::Wall => return Rgb::WHITE,
+                Tile::Water => return Rgb::new(0, 0, 255),
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_string() {
+        let mut map = HashMap::<Point2D, Tile>::new();
+        map.insert(Point2D::new(2, 1), Tile::Wall);
+        map.insert(Point2D::new(4, 2), Tile::Water);
+        let renderer = MapRenderer::new(&map);
+        assert_eq!((3, 2), renderer.get_size());
+        assert_eq!("#..\n..~\n", renderer.render_string());
+        let renderer = renderer.with_bounds(Point2D::new(2, 0), Point2D::new(4, 2))
+            .with_marker(Point2D::new(3, 0), '+', Rgb::WHITE);
+        assert_eq!(".+.\n#..\n..~\n", renderer.render_string());
+        let grid = Grid::from_fn(Point2D::new(0, 0), 2, 1, |loc| if loc.pos_x == 0 { Tile::Wall } else { Tile::Water });
+        assert_eq!("#~\n", MapRenderer::new(&grid).render_string());
+        assert_eq!("", MapRenderer::new(&HashMap::<Point2D, Tile>::new()).render_string());
+    }
+
+    #[test]
+    fn test_render_images() {
+        let grid = Grid::from_fn(Point2D::new(0, 0), 2, 1, |loc| if loc.pos_x == 0 { Tile::Wall } else { Tile::Water });
+        let mut ppm = Vec::<u8>::new();
+        MapRenderer::new(&grid).write_ppm(&mut ppm).unwrap();
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 255, 255, 0, 0, 255]);
+        assert_eq!(expected, ppm);
+        let mut pgm = Vec::<u8>::new();
+        MapRenderer::new(&grid).with_scale(2).write_pgm(&mut pgm).unwrap();
+        let mut expected = b"P5\n4 2\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 255, 29, 29, 255, 255, 29, 29]);
+        assert_eq!(expected, pgm);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod animate;
 pub mod map;
+pub mod rng;
 pub mod wristcomp;
@@ -0,0 +1,83 @@
+/// Small, fast xorshift64* pseudo-random number generator, giving reproducible sequences for a
+/// given seed without needing an external crate.
+///
+/// Not suitable for anything requiring cryptographic randomness.
+#[derive(Clone, Debug)]
+pub struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    /// Creates a new generator from the seed. Any seed (including 0) gives a valid sequence.
+    pub fn new(seed: u64) -> Self {
+        // Mix the seed so similar seeds give unrelated sequences, avoiding the all-zero state
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        return self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    /// Generates a value in the range [0, bound). Panics if the bound is 0.
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            panic!("XorShiftRng - bound must be greater than 0.");
+        }
+        // Reject values from the incomplete final interval to avoid bias
+        let zone = u64::MAX - (u64::MAX % bound);
+        loop {
+            let val = self.next_u64();
+            if val < zone {
+                return val % bound;
+            }
+        }
+    }
+
+    /// Generates a usize in the inclusive range [low, high].
+    pub fn next_in_range(&mut self, low: usize, high: usize) -> usize {
+        if low > high {
+            panic!("XorShiftRng - invalid range {}..={}.", low, high);
+        }
+        let span = (high - low) as u64;
+        if span == u64::MAX {
+            return self.next_u64() as usize;
+        }
+        return low + self.next_below(span + 1) as usize;
+    }
+
+    /// Shuffles the slice in place using the Fisher-Yates algorithm.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_deterministic() {
+        let mut rng_a = XorShiftRng::new(42);
+        let mut rng_b = XorShiftRng::new(42);
+        for _ in 0..100 {
+            assert_eq!(rng_a.next_u64(), rng_b.next_u64());
+        }
+        let mut rng = XorShiftRng::new(0);
+        for _ in 0..1000 {
+            let val = rng.next_in_range(3, 7);
+            assert!(val >= 3 && val <= 7);
+        }
+    }
+}
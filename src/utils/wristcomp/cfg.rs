@@ -0,0 +1,352 @@
+use petgraph::algo::dominators;
+use petgraph::dot::Dot;
+use petgraph::graph::DiGraph;
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::disasm::disassemble_line;
+use super::disasm::get_expression;
+use super::disasm::Expr;
+use super::disasm::Operand;
+use super::wristcomp::Instruction;
+
+/// Possible addresses of the next instruction executed after an instruction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum JumpTargets {
+    Next,               // instruction does not write to the instruction pointer
+    Static(Vec<usize>), // instruction pointer set to one of the known addresses
+    AtLeast(usize),     // unknown register added to the instruction pointer
+    Unknown,            // instruction pointer set to a value that cannot be determined
+}
+
+/// Determines where control flow can go after executing the instruction at the given address.
+///
+/// Target addresses at or beyond the end of the program mean that the program halts.
+pub fn get_jump_targets(program: &[Instruction], addr: usize, ip_reg: Option<usize>) -> JumpTargets {
+    let skips = find_skips(program, ip_reg);
+    return find_jump_targets(program, addr, ip_reg, &skips);
+}
+
+/// Determines where control flow can go after executing each instruction in the program.
+pub fn get_all_jump_targets(program: &[Instruction], ip_reg: Option<usize>) -> Vec<JumpTargets> {
+    let skips = find_skips(program, ip_reg);
+    return (0..program.len()).map(|addr| find_jump_targets(program, addr, ip_reg, &skips)).collect();
+}
+
+/// Checks if the instruction adds a register holding the result of the comparison just before it
+/// to the instruction pointer, in the "skip next instruction" pattern.
+fn is_skip_pattern(program: &[Instruction], addr: usize, ip_reg: Option<usize>) -> bool {
+    let instruction = &program[addr];
+    if addr == 0 || ip_reg.is_none() || ip_reg != Some(instruction.get_write_register()) {
+        return false;
+    }
+    let prev = &program[addr - 1];
+    match get_expression(addr, instruction, ip_reg) {
+        Expr::Binary(Operand::Reg(reg), "+", Operand::Const(offset)) |
+        Expr::Binary(Operand::Const(offset), "+", Operand::Reg(reg)) => {
+            return offset != usize::MAX && prev.get_operation().is_comparison() && prev.get_write_register() == reg;
+        },
+        _ => return false,
+    }
+}
+
+/// Finds the instructions that can only skip the next instruction or not. A skip only holds if no
+/// instruction other than its comparison can lead to it, as jumping straight to it leaves any value
+/// in the register.
+///
+/// Starts by assuming that every skip pattern holds, then rules out those that could be jumped to
+/// until no more are ruled out.
+fn find_skips(program: &[Instruction], ip_reg: Option<usize>) -> Vec<bool> {
+    let mut skips = (0..program.len()).map(|addr| is_skip_pattern(program, addr, ip_reg)).collect::<Vec<bool>>();
+    loop {
+        let jumps = (0..program.len()).map(|addr| find_jump_targets(program, addr, ip_reg, &skips))
+            .collect::<Vec<JumpTargets>>();
+        let candidates = (0..program.len()).filter(|x| skips[*x]).collect::<Vec<usize>>();
+        let mut changed = false;
+        for addr in candidates {
+            let is_jump_target = jumps.iter().enumerate().filter(|(other, _)| other + 1 != addr)
+                .any(|(_, jump)| match jump {
+                    JumpTargets::Next => false,
+                    JumpTargets::Static(targets) => targets.contains(&addr),
+                    JumpTargets::AtLeast(target) => *target <= addr,
+                    JumpTargets::Unknown => true,
+                });
+            if is_jump_target {
+                skips[addr] = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            return skips;
+        }
+    }
+}
+
+/// Determines where control flow can go after executing the instruction at the given address,
+/// given which instructions can only skip the next instruction.
+fn find_jump_targets(program: &[Instruction], addr: usize, ip_reg: Option<usize>, skips: &[bool]) -> JumpTargets {
+    let instruction = &program[addr];
+    if ip_reg.is_none() || ip_reg != Some(instruction.get_write_register()) {
+        return JumpTargets::Next;
+    }
+    // Comparison sets the instruction pointer to 0 or 1 before it is incremented
+    if instruction.get_operation().is_comparison() {
+        return JumpTargets::Static(vec![1, 2]);
+    }
+    match get_expression(addr, instruction, ip_reg) {
+        Expr::Value(Operand::Const(val)) => {
+            match val.checked_add(1) {
+                Some(target) => return JumpTargets::Static(vec![target]),
+                None => return JumpTargets::Unknown,
+            }
+        },
+        Expr::Binary(Operand::Reg(_), "+", Operand::Const(offset)) |
+        Expr::Binary(Operand::Const(offset), "+", Operand::Reg(_)) => {
+            if offset == usize::MAX {
+                return JumpTargets::Unknown;
+            }
+            // Register holding a comparison result gives the "skip next instruction" pattern
+            if skips[addr] {
+                return JumpTargets::Static(vec![offset + 1, offset.saturating_add(2)]);
+            }
+            return JumpTargets::AtLeast(offset + 1);
+        },
+        _ => return JumpTargets::Unknown,
+    }
+}
+
+/// Straight-line sequence of instructions only entered at its first instruction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BasicBlock {
+    pub start: usize,           // address of first instruction
+    pub end: usize,             // address of last instruction
+    pub jump: JumpTargets,      // control flow out of the last instruction
+    pub listing: Vec<String>,   // disassembly of the instructions in the block
+}
+
+/// Node in a control-flow graph, with all jumps out of the program leading to the exit node.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CfgNode {
+    Block(BasicBlock),
+    Exit,
+}
+
+impl fmt::Display for CfgNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CfgNode::Block(block) => {
+                for line in block.listing.iter() {
+                    writeln!(f, "{}", line)?;
+                }
+                match block.jump {
+                    JumpTargets::AtLeast(target) => return writeln!(f, "(may jump to any address >= {})", target),
+                    JumpTargets::Unknown => return writeln!(f, "(may jump to any address)"),
+                    _ => return Ok(()),
+                }
+            },
+            CfgNode::Exit => return write!(f, "exit"),
+        }
+    }
+}
+
+/// Kind of control flow along an edge of a control-flow graph.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CfgEdge {
+    Next,   // continue to the instruction following the block
+    Jump,   // instruction pointer moved anywhere else
+}
+
+impl fmt::Display for CfgEdge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CfgEdge::Next => return write!(f, "next"),
+            CfgEdge::Jump => return write!(f, "jump"),
+        }
+    }
+}
+
+/// A loop in the control-flow graph identified by a back edge to a header block dominating it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NaturalLoop {
+    pub header: NodeIndex,
+    pub latch: NodeIndex,       // source of the back edge to the header
+    pub body: Vec<NodeIndex>,   // all nodes in the loop (including header and latch)
+}
+
+/// Control-flow graph of basic blocks for a wrist computer program.
+///
+/// Indirect jumps with unknown targets are recorded on the block, but only the edges for targets
+/// that are known are included in the graph.
+pub struct ControlFlowGraph {
+    graph: DiGraph<CfgNode, CfgEdge>,
+    entry: NodeIndex,
+    exit: NodeIndex,
+    block_nodes: HashMap<usize, NodeIndex>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(program: &[Instruction], ip_reg: Option<usize>) -> Self {
+        let jumps = get_all_jump_targets(program, ip_reg);
+        // Find the leaders - first instructions of blocks
+        let mut leaders = BTreeSet::<usize>::new();
+        leaders.insert(0);
+        for (addr, jump) in jumps.iter().enumerate() {
+            match jump {
+                JumpTargets::Next => continue,
+                JumpTargets::Static(targets) => leaders.extend(targets.iter()),
+                JumpTargets::AtLeast(target) => { leaders.insert(*target); },
+                JumpTargets::Unknown => (),
+            }
+            leaders.insert(addr + 1);
+        }
+        // Create the nodes for the blocks and exit
+        let mut graph = DiGraph::<CfgNode, CfgEdge>::new();
+        let mut block_nodes = HashMap::<usize, NodeIndex>::new();
+        let starts = leaders.iter().filter(|x| **x < program.len()).copied().collect::<Vec<usize>>();
+        for (i, start) in starts.iter().enumerate() {
+            let end = if i + 1 < starts.len() { starts[i + 1] - 1 } else { program.len() - 1 };
+            let block = BasicBlock {
+                start: *start,
+                end: end,
+                jump: jumps[end].clone(),
+                listing: (*start..=end).map(|addr| disassemble_line(program, addr, ip_reg)).collect(),
+            };
+            block_nodes.insert(*start, graph.add_node(CfgNode::Block(block)));
+        }
+        let exit = graph.add_node(CfgNode::Exit);
+        let entry = *block_nodes.get(&0).unwrap_or(&exit);
+        // Add edges for the control flow out of each block
+        for start in starts.iter() {
+            let node = *block_nodes.get(start).unwrap();
+            let (end, jump) = match &graph[node] {
+                CfgNode::Block(block) => (block.end, block.jump.clone()),
+                CfgNode::Exit => continue,
+            };
+            let targets = match jump {
+                JumpTargets::Next => vec![end + 1],
+                JumpTargets::Static(targets) => targets,
+                JumpTargets::AtLeast(target) => vec![target],
+                JumpTargets::Unknown => vec![],
+            };
+            for target in targets {
+                let target_node = *block_nodes.get(&target).unwrap_or(&exit);
+                if graph.find_edge(node, target_node).is_none() {
+                    let kind = if target == end + 1 { CfgEdge::Next } else { CfgEdge::Jump };
+                    graph.add_edge(node, target_node, kind);
+                }
+            }
+        }
+        return Self {
+            graph: graph,
+            entry: entry,
+            exit: exit,
+            block_nodes: block_nodes,
+        };
+    }
+
+    pub fn get_graph(&self) -> &DiGraph<CfgNode, CfgEdge> {
+        return &self.graph;
+    }
+
+    pub fn get_entry(&self) -> NodeIndex {
+        return self.entry;
+    }
+
+    pub fn get_exit(&self) -> NodeIndex {
+        return self.exit;
+    }
+
+    /// Gets the node for the basic block starting at the given address.
+    pub fn get_block_node(&self, start: usize) -> Option<NodeIndex> {
+        return self.block_nodes.get(&start).copied();
+    }
+
+    /// Gets the basic block held in the given node, or None for the exit node.
+    pub fn get_block(&self, node: NodeIndex) -> Option<&BasicBlock> {
+        match &self.graph[node] {
+            CfgNode::Block(block) => return Some(block),
+            CfgNode::Exit => return None,
+        }
+    }
+
+    /// Exports the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        return format!("{}", Dot::new(&self.graph));
+    }
+
+    /// Finds the natural loops in the graph, sorted by address of the loop header. Nodes that
+    /// cannot be reached from the entry are not included in any loop.
+    pub fn find_natural_loops(&self) -> Vec<NaturalLoop> {
+        let doms = dominators::simple_fast(&self.graph, self.entry);
+        let mut output = Vec::<NaturalLoop>::new();
+        for edge in self.graph.edge_indices() {
+            let (latch, header) = self.graph.edge_endpoints(edge).unwrap();
+            let is_back_edge = match doms.dominators(latch) {
+                Some(mut iter) => iter.any(|x| x == header),
+                None => false,
+            };
+            if !is_back_edge {
+                continue;
+            }
+            // Body is everything reaching the latch without passing through the header
+            let mut body = BTreeSet::<NodeIndex>::new();
+            body.insert(header);
+            let mut stack = vec![latch];
+            while let Some(node) = stack.pop() {
+                if body.insert(node) {
+                    stack.extend(self.graph.neighbors_directed(node, Direction::Incoming));
+                }
+            }
+            output.push(NaturalLoop {
+                header: header,
+                latch: latch,
+                body: body.into_iter().collect(),
+            });
+        }
+        output.sort_by_key(|x| (self.get_block(x.header).map(|b| b.start), x.latch));
+        return output;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::program::Program;
+
+    #[test]
+    fn test_cfg_d19() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let cfg = ControlFlowGraph::new(program.get_instructions(), program.get_ip_reg());
+        let starts = cfg.get_graph().node_indices().filter_map(|x| cfg.get_block(x)).map(|x| x.start)
+            .collect::<Vec<usize>>();
+        assert_eq!(vec![0, 1, 2, 3, 6, 7, 8, 11, 12, 15, 16, 17, 26, 27], starts);
+        assert_eq!(JumpTargets::AtLeast(26), get_jump_targets(program.get_instructions(), 25, program.get_ip_reg()));
+        // Exit only reached by "mulr 4 4 4" at address 16
+        let exit_preds = cfg.get_graph().neighbors_directed(cfg.get_exit(), Direction::Incoming)
+            .collect::<Vec<NodeIndex>>();
+        assert_eq!(vec![cfg.get_block_node(16).unwrap()], exit_preds);
+        // Inner loop over r1 and outer loop over r3
+        let loops = cfg.find_natural_loops();
+        assert_eq!(2, loops.len());
+        let body_starts = |x: &NaturalLoop| x.body.iter().map(|n| cfg.get_block(*n).unwrap().start)
+            .collect::<BTreeSet<usize>>().into_iter().collect::<Vec<usize>>();
+        assert_eq!(vec![2, 3, 6, 7, 8, 11, 12, 15], body_starts(&loops[0]));
+        assert_eq!(vec![3, 6, 7, 8, 11], body_starts(&loops[1]));
+        assert!(cfg.to_dot().starts_with("digraph {"));
+    }
+
+    #[test]
+    fn test_jump_onto_skip() {
+        // "addr 3 4 4" at address 3 skips address 4 if the comparison before it holds
+        let input = "#ip 4\nseti 5 0 1\neqri 1 5 3\naddr 3 4 4\nseti 0 0 0\nseti 1 0 2";
+        let program = input.parse::<Program>().unwrap();
+        assert_eq!(JumpTargets::Static(vec![3, 4]), get_jump_targets(program.get_instructions(), 2, program.get_ip_reg()));
+        // Jumping straight to the skip bypasses the comparison, so any later address could follow
+        let program = format!("{}\nseti 1 0 4", input).parse::<Program>().unwrap();
+        assert_eq!(JumpTargets::AtLeast(3), get_jump_targets(program.get_instructions(), 2, program.get_ip_reg()));
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+
+/// Operand of an instruction as seen in pseudo-code, with reads of the instruction pointer
+/// register replaced by the (known) address of the instruction.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) enum Operand {
+    Const(usize),
+    Reg(usize),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Const(val) => return write!(f, "{}", val),
+            Operand::Reg(reg) => return write!(f, "r{}", reg),
+        }
+    }
+}
+
+/// Right-hand side of the assignment made by an instruction.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) enum Expr {
+    Value(Operand),
+    Binary(Operand, &'static str, Operand),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Value(val) => return write!(f, "{}", val),
+            Expr::Binary(left, symbol, right) => return write!(f, "{} {} {}", left, symbol, right),
+        }
+    }
+}
+
+fn get_operand(val: usize, is_reg: bool, addr: usize, ip_reg: Option<usize>) -> Operand {
+    if !is_reg {
+        return Operand::Const(val);
+    }
+    if ip_reg == Some(val) {
+        return Operand::Const(addr);
+    }
+    return Operand::Reg(val);
+}
+
+/// Gets the expression for the value written by the instruction at the given address, folding it
+/// into a constant where possible.
+pub(super) fn get_expression(addr: usize, instruction: &Instruction, ip_reg: Option<usize>) -> Expr {
+    let op = instruction.get_operation();
+    let (a, b, _c) = instruction.get_values();
+    let left = get_operand(a, op.is_reg_a(), addr, ip_reg);
+    let right = get_operand(b, op.is_reg_b(), addr, ip_reg);
+    let symbol = match op {
+        Operation::SetReg | Operation::SetImm => return Expr::Value(left),
+        Operation::AddReg | Operation::AddImm => "+",
+        Operation::MulReg | Operation::MulImm => "*",
+        Operation::BitANDReg | Operation::BitANDImm => "&",
+        Operation::BitORReg | Operation::BitORImm => "|",
+        Operation::GtImmReg | Operation::GtRegImm | Operation::GtRegReg => ">",
+        Operation::EqImmReg | Operation::EqRegImm | Operation::EqRegReg => "==",
+    };
+    // Fold the expression if both operands are known
+    if let (Operand::Const(x), Operand::Const(y)) = (left, right) {
+        let folded = match symbol {
+            "+" => x.checked_add(y),
+            "*" => x.checked_mul(y),
+            "&" => Some(x & y),
+            "|" => Some(x | y),
+            ">" => Some((x > y) as usize),
+            _ => Some((x == y) as usize),
+        };
+        if let Some(val) = folded {
+            return Expr::Value(Operand::Const(val));
+        }
+    }
+    return Expr::Binary(left, symbol, right);
+}
+
+/// Generates the pseudo-code for the instruction at the given address of the program.
+///
+/// Writes to the instruction pointer register are shown as jumps, with the comparison made by the
+/// previous instruction shown as the condition for "skip next instruction" jumps.
+pub fn decompile_instruction(program: &[Instruction], addr: usize, ip_reg: Option<usize>) -> String {
+    let instruction = &program[addr];
+    let expr = get_expression(addr, instruction, ip_reg);
+    let dest = instruction.get_write_register();
+    if ip_reg != Some(dest) {
+        match expr {
+            Expr::Binary(..) if instruction.get_operation().is_comparison() => {
+                return format!("r{} = ({}) ? 1 : 0", dest, expr);
+            },
+            _ => return format!("r{} = {}", dest, expr),
+        }
+    }
+    // Instruction pointer is incremented after the instruction writes to it
+    match expr {
+        Expr::Value(Operand::Const(val)) if val.checked_add(1).is_some() => {
+            let target = val + 1;
+            if target >= program.len() {
+                return format!("goto {} (halt)", target);
+            }
+            return format!("goto {}", target);
+        },
+        Expr::Binary(Operand::Reg(reg), "+", Operand::Const(offset)) |
+        Expr::Binary(Operand::Const(offset), "+", Operand::Reg(reg)) if offset.checked_add(2).is_some() => {
+            // Check if the register added to the instruction pointer holds a comparison result
+            if addr > 0 {
+                let prev = &program[addr - 1];
+                if prev.get_operation().is_comparison() && prev.get_write_register() == reg {
+                    let condition = get_expression(addr - 1, prev, ip_reg);
+                    if offset == addr {
+                        return format!("if {} goto {}", condition, offset + 2);
+                    }
+                    return format!("if {} goto {} else goto {}", condition, offset + 2, offset + 1);
+                }
+            }
+            return format!("goto r{} + {}", reg, offset + 1);
+        },
+        _ => return format!("goto ({}) + 1", expr),
+    }
+}
+
+/// Produces the listing line for the instruction at the given address, made up of the address,
+/// the instruction itself and its pseudo-code.
+pub fn disassemble_line(program: &[Instruction], addr: usize, ip_reg: Option<usize>) -> String {
+    let text = program[addr].to_string();
+    return format!("{:>4}: {:<16} {}", addr, text, decompile_instruction(program, addr, ip_reg));
+}
+
+/// Produces a readable listing of the whole program, one instruction per line.
+pub fn disassemble(program: &[Instruction], ip_reg: Option<usize>) -> String {
+    let mut output = String::new();
+    if let Some(reg) = ip_reg {
+        output.push_str(&format!("#ip {}\n", reg));
+    }
+    for addr in 0..program.len() {
+        output.push_str(&disassemble_line(program, addr, ip_reg));
+        output.push('\n');
+    }
+    return output;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::program::Program;
+    use super::super::wristcomp::Operation;
+
+    #[test]
+    fn test_disassemble_d19() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let ip_reg = program.get_ip_reg();
+        let instructions = program.get_instructions();
+        assert_eq!("goto 17", decompile_instruction(instructions, 0, ip_reg));
+        assert_eq!("r2 = r3 * r1", decompile_instruction(instructions, 3, ip_reg));
+        assert_eq!("r2 = (r2 == r5) ? 1 : 0", decompile_instruction(instructions, 4, ip_reg));
+        assert_eq!("if r2 == r5 goto 7", decompile_instruction(instructions, 5, ip_reg));
+        assert_eq!("goto 8", decompile_instruction(instructions, 6, ip_reg));
+        assert_eq!("if r1 > r5 goto 12", decompile_instruction(instructions, 10, ip_reg));
+        assert_eq!("goto 257 (halt)", decompile_instruction(instructions, 16, ip_reg));
+        assert_eq!("goto r0 + 26", decompile_instruction(instructions, 25, ip_reg));
+        assert_eq!("r2 = 27", decompile_instruction(instructions, 27, ip_reg));
+        let listing = disassemble(instructions, ip_reg);
+        assert_eq!(instructions.len() + 1, listing.lines().count());
+        assert_eq!("   5: addr 2 4 4       if r2 == r5 goto 7", listing.lines().nth(6).unwrap());
+    }
+
+    #[test]
+    fn test_decompile_jump_overflow() {
+        let program = vec![
+            Instruction::new(Operation::SetImm, (usize::MAX, 0, 4)),
+            Instruction::new(Operation::AddImm, (1, usize::MAX, 4)),
+        ];
+        assert_eq!(format!("goto ({}) + 1", usize::MAX), decompile_instruction(&program, 0, Some(4)));
+        assert_eq!(format!("goto (r1 + {}) + 1", usize::MAX), decompile_instruction(&program, 1, Some(4)));
+    }
+}
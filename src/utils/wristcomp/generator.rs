@@ -0,0 +1,144 @@
+use enum_iterator::IntoEnumIterator;
+
+use std::collections::HashMap;
+
+use super::inference::OpSample;
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+use super::wristcomp::WristComputer;
+use super::super::rng::XorShiftRng;
+
+/// Number of registers in the generated samples, matching the Day 16 puzzle input.
+const DEFAULT_REGISTER_COUNT: usize = 4;
+
+/// Largest register value or immediate operand in the generated samples unless otherwise specified.
+const DEFAULT_MAX_VALUE: usize = 15;
+
+/// Generates random Day 16 style samples of instructions executed by the wrist computer, using a
+/// seeded pseudo-random number generator so the output is reproducible.
+pub struct SampleGenerator {
+    rng: XorShiftRng,
+    num_registers: usize,
+    max_value: usize,
+}
+
+impl SampleGenerator {
+    pub fn new(seed: u64) -> Self {
+        return SampleGenerator::with_limits(seed, DEFAULT_REGISTER_COUNT, DEFAULT_MAX_VALUE);
+    }
+
+    /// Creates a new sample generator with the given number of registers, and with all register
+    /// values and immediate operands no greater than the maximum value.
+    pub fn with_limits(seed: u64, num_registers: usize, max_value: usize) -> Self {
+        if num_registers == 0 {
+            panic!("SampleGenerator - must have at least one register.");
+        }
+        Self {
+            rng: XorShiftRng::new(seed),
+            num_registers: num_registers,
+            max_value: max_value,
+        }
+    }
+
+    /// Generates a random mapping of the opcodes 0 to 15 onto the operations.
+    pub fn generate_mapping(&mut self) -> HashMap<usize, Operation> {
+        let mut ops = Operation::into_enum_iter().collect::<Vec<Operation>>();
+        self.rng.shuffle(&mut ops);
+        return ops.into_iter().enumerate().collect();
+    }
+
+    /// Generates a sample of the operation being executed with the given opcode on random registers.
+    pub fn generate_sample(&mut self, opcode: usize, op: Operation) -> OpSample {
+        let reg_before = (0..self.num_registers)
+            .map(|_| self.rng.next_in_range(0, self.max_value)).collect::<Vec<usize>>();
+        let a = self.generate_operand(op.is_reg_a());
+        let b = self.generate_operand(op.is_reg_b());
+        let c = self.generate_operand(true);
+        let reg_after = WristComputer::perform_operation(&reg_before, &Instruction::new(op, (a, b, c)));
+        return OpSample::new(reg_before, reg_after, vec![opcode, a, b, c]);
+    }
+
+    /// Generates the given number of samples for the opcode mapping, cycling through the opcodes
+    /// in a random order so each opcode gets a similar number of samples.
+    pub fn generate_samples(&mut self, mapping: &HashMap<usize, Operation>, count: usize) -> Vec<OpSample> {
+        let mut opcodes = mapping.keys().copied().collect::<Vec<usize>>();
+        opcodes.sort_unstable();
+        let mut samples = Vec::<OpSample>::new();
+        while samples.len() < count {
+            self.rng.shuffle(&mut opcodes);
+            for opcode in opcodes.iter().take(count - samples.len()) {
+                samples.push(self.generate_sample(*opcode, mapping[opcode]));
+            }
+        }
+        return samples;
+    }
+
+    /// Generates a register index if the operand is a register, otherwise an immediate value.
+    fn generate_operand(&mut self, is_reg: bool) -> usize {
+        if is_reg {
+            return self.rng.next_in_range(0, self.num_registers - 1);
+        }
+        return self.rng.next_in_range(0, self.max_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::inference::minimise_samples;
+    use super::super::inference::OpcodeInference;
+
+    #[test]
+    fn test_generated_mapping_inferred() {
+        for seed in 0..20 {
+            let mut generator = SampleGenerator::new(seed);
+            let mapping = generator.generate_mapping();
+            let samples = generator.generate_samples(&mapping, 400);
+            assert_eq!(Ok(mapping), OpcodeInference::new(&samples).resolve(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_generated_samples_minimised() {
+        let mut generator = SampleGenerator::new(2018);
+        let mapping = generator.generate_mapping();
+        let samples = generator.generate_samples(&mapping, 200);
+        let minimised = minimise_samples(&samples).unwrap();
+        assert!(minimised.len() < samples.len());
+        assert_eq!(Ok(mapping.clone()), OpcodeInference::new(&minimised).resolve());
+        // Removing any single sample leaves the mapping undetermined
+        for i in 0..minimised.len() {
+            let mut reduced = minimised.clone();
+            reduced.remove(i);
+            assert_ne!(Ok(mapping.clone()), OpcodeInference::new(&reduced).resolve());
+        }
+    }
+
+    #[test]
+    fn test_generated_samples_minimum_size() {
+        // Compare against every subset of a small sample set for a partial mapping
+        let mut checked = 0;
+        for seed in 0..10 {
+            let mut generator = SampleGenerator::new(seed);
+            let mapping = generator.generate_mapping().into_iter().filter(|(opcode, _)| *opcode < 4)
+                .collect::<HashMap<usize, Operation>>();
+            let samples = generator.generate_samples(&mapping, 16);
+            if OpcodeInference::new(&samples).resolve() != Ok(mapping.clone()) {
+                continue;
+            }
+            let resolves = |subset: u32| {
+                let kept = (0..samples.len()).filter(|i| subset & (1 << i) != 0)
+                    .map(|i| samples[i].clone()).collect::<Vec<OpSample>>();
+                return OpcodeInference::new(&kept).resolve() == Ok(mapping.clone());
+            };
+            let smallest = (1..=samples.len())
+                .find(|size| (0..1u32 << samples.len()).any(|subset| subset.count_ones() as usize == *size && resolves(subset)))
+                .unwrap();
+            let minimised = minimise_samples(&samples).unwrap();
+            assert_eq!(smallest, minimised.len(), "seed {}", seed);
+            assert_eq!(Ok(mapping.clone()), OpcodeInference::new(&minimised).resolve(), "seed {}", seed);
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use super::registers::RegisterFile;
+
+/// Changes made by a single step of the wrist computer, recorded so the step can be undone.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) enum UndoEntry {
+    /// Instruction overwriting a single register (after the ip register was written).
+    Write { ip_val: usize, ip_reg_old: Option<(usize, usize)>, dest: usize, dest_old: usize },
+    /// Macro-op that can change any of the registers.
+    Registers { ip_val: usize, registers: RegisterFile },
+}
+
+/// Log of undo entries for the most recent steps, discarding the oldest entries once full.
+pub(super) struct UndoLog {
+    entries: VecDeque<UndoEntry>,
+    limit: Option<usize>,
+}
+
+impl UndoLog {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit: limit,
+        }
+    }
+
+    pub fn push(&mut self, entry: UndoEntry) {
+        if self.limit == Some(0) {
+            return;
+        }
+        if let Some(limit) = self.limit {
+            if self.entries.len() >= limit {
+                self.entries.pop_front();
+            }
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        return self.entries.pop_back();
+    }
+
+    pub fn get_limit(&self) -> Option<usize> {
+        return self.limit;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+}
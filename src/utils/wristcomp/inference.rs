@@ -0,0 +1,415 @@
+use enum_iterator::IntoEnumIterator;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+use super::wristcomp::WristComputer;
+
+/// Observed effect of a single instruction with an unknown opcode, made up of the registers before
+/// and after the instruction was executed along with the raw "opcode a b c" instruction values.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OpSample {
+    reg_before: Vec<usize>,
+    reg_after: Vec<usize>,
+    instruction: Vec<usize>
+}
+
+impl OpSample {
+    pub fn new(reg_before: Vec<usize>, reg_after: Vec<usize>, instruction: Vec<usize>) -> Self {
+        Self {
+            reg_before: reg_before,
+            reg_after: reg_after,
+            instruction: instruction
+        }
+    }
+
+    pub fn get_opcode(&self) -> usize {
+        return self.instruction[0];
+    }
+
+    pub fn get_reg_before(&self) -> Vec<usize> {
+        return self.reg_before.clone();
+    }
+
+    pub fn get_reg_after(&self) -> Vec<usize> {
+        return self.reg_after.clone();
+    }
+
+    pub fn get_values(&self) -> (usize, usize, usize) {
+        return (self.instruction[1], self.instruction[2], self.instruction[3]);
+    }
+
+    /// Checks if performing the given operation with the sample instruction values produces the
+    /// sample output.
+    pub fn matches(&self, op: Operation) -> bool {
+        let instruction = Instruction::new(op, self.get_values());
+        match WristComputer::try_perform_operation(&self.reg_before, &instruction) {
+            Ok(output) => return output == self.reg_after,
+            Err(_) => return false, // operation can't be applied to the sample registers
+        }
+    }
+
+    /// Gets all operations that are consistent with the sample.
+    pub fn get_matching_operations(&self) -> Vec<Operation> {
+        return Operation::into_enum_iter().filter(|op| self.matches(*op)).collect();
+    }
+}
+
+/// Reasons an opcode mapping could not be determined from a set of samples.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InferenceError {
+    /// No operation reproduces the sample with the given index.
+    UnmatchedSample { sample: usize, opcode: usize },
+    /// The samples for the opcode disagree, so no single operation is consistent with all of them.
+    NoCandidates(usize),
+    /// Every opcode has candidates, but they cannot all be given different operations.
+    NoConsistentMapping,
+    /// More than one mapping is consistent, with the listed opcodes having more than one possible
+    /// operation.
+    Ambiguous(Vec<(usize, Vec<Operation>)>),
+}
+
+impl fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InferenceError::UnmatchedSample { sample, opcode } => {
+                return write!(f, "sample {} (opcode {}) is not reproduced by any operation", sample, opcode);
+            },
+            InferenceError::NoCandidates(opcode) => {
+                return write!(f, "no operation is consistent with every sample for opcode {}", opcode);
+            },
+            InferenceError::NoConsistentMapping => {
+                return write!(f, "opcodes cannot each be mapped to a different operation");
+            },
+            InferenceError::Ambiguous(opcodes) => {
+                write!(f, "mapping is ambiguous")?;
+                for (i, (opcode, ops)) in opcodes.iter().enumerate() {
+                    let mnemonics = ops.iter().map(|op| op.to_mnemonic()).collect::<Vec<&str>>();
+                    let sep = if i == 0 { ": " } else { "; " };
+                    write!(f, "{}opcode {} could be {}", sep, opcode, mnemonics.join(", "))?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Error for InferenceError {}
+
+/// Determines the mappings of opcodes to operations that are consistent with a set of samples,
+/// where each opcode seen in the samples maps to a different operation.
+pub struct OpcodeInference {
+    candidates: BTreeMap<usize, Vec<Operation>>,
+    unmatched: Vec<(usize, usize)>,
+}
+
+impl OpcodeInference {
+    pub fn new(samples: &[OpSample]) -> Self {
+        let matches = samples.iter().map(|x| (x.get_opcode(), x.get_matching_operations()));
+        return OpcodeInference::from_matching_operations(matches);
+    }
+
+    /// Creates the inference from the opcode and matching operations of each sample, in order.
+    fn from_matching_operations<I>(matches: I) -> Self where I: Iterator<Item = (usize, Vec<Operation>)> {
+        let mut candidates = BTreeMap::<usize, Vec<Operation>>::new();
+        let mut unmatched = Vec::<(usize, usize)>::new();
+        for (i, (opcode, matching)) in matches.enumerate() {
+            if matching.is_empty() {
+                unmatched.push((i, opcode));
+            }
+            // Narrow the candidates for the opcode to those consistent with every sample
+            candidates.entry(opcode)
+                .and_modify(|ops| ops.retain(|op| matching.contains(op)))
+                .or_insert(matching);
+        }
+        Self {
+            candidates: candidates,
+            unmatched: unmatched,
+        }
+    }
+
+    /// Gets the operations consistent with every sample for each opcode, ignoring the other opcodes.
+    pub fn get_candidates(&self) -> &BTreeMap<usize, Vec<Operation>> {
+        return &self.candidates;
+    }
+
+    /// Finds every mapping of opcodes to distinct operations that is consistent with the samples,
+    /// stopping once the limit (if given) is reached.
+    pub fn find_mappings(&self, limit: Option<usize>) -> Vec<HashMap<usize, Operation>> {
+        let mut found = Vec::<HashMap<usize, Operation>>::new();
+        self.search(&HashMap::new(), limit, &mut found);
+        return found;
+    }
+
+    /// Gets the operations the opcode maps to in at least one consistent mapping.
+    pub fn get_feasible_operations(&self, opcode: usize) -> Vec<Operation> {
+        let candidates = match self.candidates.get(&opcode) {
+            Some(candidates) => candidates,
+            None => return vec![],
+        };
+        let mut feasible = Vec::<Operation>::new();
+        for op in candidates {
+            let mut fixed = HashMap::<usize, Operation>::new();
+            fixed.insert(opcode, *op);
+            let mut found = Vec::<HashMap<usize, Operation>>::new();
+            self.search(&fixed, Some(1), &mut found);
+            if !found.is_empty() {
+                feasible.push(*op);
+            }
+        }
+        return feasible;
+    }
+
+    /// Determines the single mapping consistent with the samples, or reports why the samples are
+    /// contradictory or ambiguous.
+    pub fn resolve(&self) -> Result<HashMap<usize, Operation>, InferenceError> {
+        if let Some((sample, opcode)) = self.unmatched.first() {
+            return Err(InferenceError::UnmatchedSample { sample: *sample, opcode: *opcode });
+        }
+        if let Some((opcode, _)) = self.candidates.iter().find(|(_, ops)| ops.is_empty()) {
+            return Err(InferenceError::NoCandidates(*opcode));
+        }
+        let mut mappings = self.find_mappings(Some(2));
+        match mappings.len() {
+            0 => return Err(InferenceError::NoConsistentMapping),
+            1 => return Ok(mappings.pop().unwrap()),
+            _ => {
+                let ambiguous = self.candidates.keys()
+                    .map(|opcode| (*opcode, self.get_feasible_operations(*opcode)))
+                    .filter(|(_, ops)| ops.len() > 1)
+                    .collect::<Vec<(usize, Vec<Operation>)>>();
+                return Err(InferenceError::Ambiguous(ambiguous));
+            }
+        }
+    }
+
+    /// Backtracking search extending the partial mapping to all opcodes, assigning the opcode with
+    /// the fewest remaining candidates first.
+    fn search(&self, mapping: &HashMap<usize, Operation>, limit: Option<usize>,
+            found: &mut Vec<HashMap<usize, Operation>>) {
+        if limit.is_some_and(|limit| found.len() >= limit) {
+            return;
+        }
+        let used = mapping.values().copied().collect::<HashSet<Operation>>();
+        let mut next: Option<(usize, Vec<Operation>)> = None;
+        for (opcode, ops) in self.candidates.iter() {
+            if mapping.contains_key(opcode) {
+                continue;
+            }
+            let remaining = ops.iter().copied().filter(|op| !used.contains(op)).collect::<Vec<Operation>>();
+            if next.as_ref().is_none_or(|(_, best)| remaining.len() < best.len()) {
+                next = Some((*opcode, remaining));
+            }
+        }
+        let (opcode, remaining) = match next {
+            Some(next) => next,
+            None => {
+                found.push(mapping.clone());
+                return;
+            }
+        };
+        for op in remaining {
+            let mut extended = mapping.clone();
+            extended.insert(opcode, op);
+            self.search(&extended, limit, found);
+        }
+    }
+}
+
+/// Finds a smallest subset of the samples that still determines the same single opcode mapping,
+/// with the samples kept in their original order. Returns an error if the full sample set does not
+/// determine a single mapping.
+///
+/// A subset determines the mapping if it has a sample for every opcode and no group of opcodes can
+/// swap operations with each other, or move to an operation no opcode uses. Only the intersection
+/// of the matching operations of the samples kept for each opcode matters, so the cheapest way to
+/// reach each intersection is found for every opcode, then an iterative deepening search picks one
+/// intersection per opcode with the fewest samples in total.
+pub fn minimise_samples(samples: &[OpSample]) -> Result<Vec<OpSample>, InferenceError> {
+    let mapping = OpcodeInference::new(samples).resolve()?;
+    let ops = Operation::into_enum_iter().collect::<Vec<Operation>>();
+    let get_mask = |matching: &[Operation]| {
+        return ops.iter().enumerate().filter(|(_, op)| matching.contains(op)).fold(0u32, |mask, (i, _)| mask | 1 << i);
+    };
+    // Give each opcode a node index, and find the node (if any) each operation is mapped from
+    let mut opcodes = mapping.keys().copied().collect::<Vec<usize>>();
+    opcodes.sort_unstable();
+    let owners = ops.iter().map(|op| opcodes.iter().position(|opcode| mapping[opcode] == *op)).collect::<Vec<Option<usize>>>();
+    let used_mask = get_mask(&mapping.values().copied().collect::<Vec<Operation>>());
+    let mut options = Vec::<Vec<SampleChoice>>::new();
+    for opcode in opcodes.iter() {
+        let sample_masks = samples.iter().enumerate()
+            .filter(|(_, x)| x.get_opcode() == *opcode)
+            .map(|(i, x)| (i, get_mask(&x.get_matching_operations())));
+        options.push(find_sample_choices(sample_masks, used_mask, &owners));
+    }
+    // Search the most constrained opcodes first
+    let mut order = (0..opcodes.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|node| options[*node].len());
+    let mut min_remaining = vec![0; order.len() + 1];
+    for i in (0..order.len()).rev() {
+        let cheapest = options[order[i]].iter().map(|x| x.samples.len()).min().unwrap_or(0);
+        min_remaining[i] = min_remaining[i + 1] + cheapest;
+    }
+    // Full sample set is always a solution, so the search stops by the time it reaches that size
+    let mut chosen = Vec::<usize>::new();
+    let mut budget = min_remaining[0];
+    while !search_sample_choices(&options, &order, &min_remaining, 0, budget, &mut vec![0; opcodes.len()], &mut chosen) {
+        budget += 1;
+    }
+    chosen.sort_unstable();
+    return Ok(chosen.into_iter().map(|i| samples[i].clone()).collect());
+}
+
+/// Set of samples for one opcode, along with the other opcodes whose operations are still
+/// consistent with all of them.
+struct SampleChoice {
+    samples: Vec<usize>,
+    others: u32,
+}
+
+/// Finds the cheapest sets of samples for an opcode, given the index and matching operations mask
+/// of each of its samples. Sets leaving any operation not used by the mapping are excluded, as are
+/// those that are never better than a cheaper set.
+fn find_sample_choices<I>(sample_masks: I, used_mask: u32, owners: &[Option<usize>]) -> Vec<SampleChoice>
+        where I: Iterator<Item = (usize, u32)> {
+    // Breadth-first search over intersections, so each is first reached with the fewest samples
+    let mut singles = Vec::<(usize, u32)>::new();
+    for (i, mask) in sample_masks {
+        if singles.iter().all(|(_, x)| *x != mask) {
+            singles.push((i, mask));
+        }
+    }
+    let mut reached = BTreeMap::<u32, Vec<usize>>::new();
+    let mut frontier = Vec::<u32>::new();
+    for (i, mask) in singles.iter() {
+        reached.insert(*mask, vec![*i]);
+        frontier.push(*mask);
+    }
+    while !frontier.is_empty() {
+        let mut next = Vec::<u32>::new();
+        for mask in frontier {
+            for (i, single) in singles.iter() {
+                let narrowed = mask & single;
+                if !reached.contains_key(&narrowed) {
+                    let mut chosen = reached[&mask].clone();
+                    chosen.push(*i);
+                    reached.insert(narrowed, chosen);
+                    next.push(narrowed);
+                }
+            }
+        }
+        frontier = next;
+    }
+    // Drop sets that leave a superset of the operations of another set with no more samples
+    let valid = reached.into_iter().filter(|(mask, _)| mask & !used_mask == 0).collect::<Vec<(u32, Vec<usize>)>>();
+    let mut choices = Vec::<SampleChoice>::new();
+    for (mask, chosen) in valid.iter() {
+        let dominated = valid.iter().any(|(other, other_chosen)| {
+            return other != mask && other & !mask == 0 && other_chosen.len() <= chosen.len();
+        });
+        if dominated {
+            continue;
+        }
+        let others = (0..owners.len()).filter(|i| mask & (1 << i) != 0).filter_map(|i| owners[i])
+            .fold(0u32, |others, node| others | 1 << node);
+        choices.push(SampleChoice { samples: chosen.clone(), others: others });
+    }
+    choices.sort_by_key(|x| x.samples.len());
+    return choices;
+}
+
+/// Depth-first search for a choice of samples for each remaining opcode (in the given order) using
+/// at most the budgeted number of samples in total, such that no opcodes could swap operations.
+/// Edges record the other opcodes each chosen opcode could take the operation of.
+fn search_sample_choices(options: &[Vec<SampleChoice>], order: &[usize], min_remaining: &[usize], depth: usize,
+        budget: usize, edges: &mut Vec<u32>, chosen: &mut Vec<usize>) -> bool {
+    if depth == order.len() {
+        return true;
+    }
+    if min_remaining[depth] > budget {
+        return false;
+    }
+    let node = order[depth];
+    for choice in options[node].iter() {
+        if choice.samples.len() + min_remaining[depth + 1] > budget {
+            break;
+        }
+        // Opcodes could swap operations if there is a cycle through this opcode
+        edges[node] = choice.others & !(1 << node);
+        if can_reach(edges, edges[node], node) {
+            continue;
+        }
+        let chosen_len = chosen.len();
+        chosen.extend(choice.samples.iter());
+        if search_sample_choices(options, order, min_remaining, depth + 1, budget - choice.samples.len(), edges, chosen) {
+            return true;
+        }
+        chosen.truncate(chosen_len);
+    }
+    edges[node] = 0;
+    return false;
+}
+
+/// Checks if the target node can be reached by following edges from any of the start nodes.
+fn can_reach(edges: &[u32], start: u32, target: usize) -> bool {
+    let mut visited = 0u32;
+    let mut pending = start;
+    while pending != 0 {
+        let node = pending.trailing_zeros() as usize;
+        pending &= pending - 1;
+        if node == target {
+            return true;
+        }
+        if visited & (1 << node) == 0 {
+            visited |= 1 << node;
+            pending |= edges[node] & !visited;
+        }
+    }
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inference_example() {
+        // Example sample from AoC 2018 Day 16 - behaves like mulr, addi and seti
+        let samples = vec![OpSample::new(vec![3, 2, 1, 1], vec![3, 2, 2, 1], vec![9, 2, 1, 2])];
+        assert_eq!(vec![Operation::AddImm, Operation::MulReg, Operation::SetImm], samples[0].get_matching_operations());
+        let inference = OpcodeInference::new(&samples);
+        assert_eq!(3, inference.find_mappings(None).len());
+        let err = inference.resolve().unwrap_err();
+        assert_eq!("mapping is ambiguous: opcode 9 could be addi, mulr, seti", err.to_string());
+    }
+
+    #[test]
+    fn test_inference_errors() {
+        let samples = vec![
+            OpSample::new(vec![3, 2, 1, 1], vec![3, 2, 2, 1], vec![9, 2, 1, 2]),
+            OpSample::new(vec![0, 0, 0, 0], vec![0, 0, 5, 0], vec![9, 5, 0, 2]),
+        ];
+        // Only seti consistent with both samples
+        let mapping = OpcodeInference::new(&samples).resolve().unwrap();
+        assert_eq!(Some(&Operation::SetImm), mapping.get(&9));
+        // Two opcodes that can only be seti
+        let mut conflicting = samples.clone();
+        conflicting.push(OpSample::new(vec![0, 0, 0, 0], vec![0, 0, 5, 0], vec![4, 5, 0, 2]));
+        conflicting.push(OpSample::new(vec![3, 2, 1, 1], vec![3, 2, 2, 1], vec![4, 2, 1, 2]));
+        assert_eq!(Err(InferenceError::NoConsistentMapping), OpcodeInference::new(&conflicting).resolve());
+        // Samples disagreeing on the opcode
+        let mut disagreeing = samples.clone();
+        disagreeing.push(OpSample::new(vec![1, 2, 0, 0], vec![1, 2, 3, 0], vec![9, 0, 1, 2]));
+        assert_eq!(Err(InferenceError::NoCandidates(9)), OpcodeInference::new(&disagreeing).resolve());
+        // Sample that no operation can produce
+        let impossible = vec![OpSample::new(vec![0, 0, 0, 0], vec![1, 1, 1, 1], vec![2, 0, 0, 0])];
+        let err = OpcodeInference::new(&impossible).resolve().unwrap_err();
+        assert_eq!(InferenceError::UnmatchedSample { sample: 0, opcode: 2 }, err);
+    }
+}
@@ -1,5 +1,62 @@
+mod cfg;
+mod disasm;
+mod generator;
+mod history;
+mod inference;
+mod optimise;
+mod profile;
+mod program;
+mod registers;
+mod snapshot;
+mod symbolic;
+mod trace;
 mod wristcomp;
 
+pub use self::cfg::BasicBlock;
+pub use self::cfg::CfgEdge;
+pub use self::cfg::CfgNode;
+pub use self::cfg::ControlFlowGraph;
+pub use self::cfg::get_all_jump_targets;
+pub use self::cfg::get_jump_targets;
+pub use self::cfg::JumpTargets;
+pub use self::cfg::NaturalLoop;
+pub use self::disasm::decompile_instruction;
+pub use self::disasm::disassemble;
+pub use self::disasm::disassemble_line;
+pub use self::generator::SampleGenerator;
+pub use self::inference::InferenceError;
+pub use self::inference::minimise_samples;
+pub use self::inference::OpcodeInference;
+pub use self::inference::OpSample;
+pub use self::wristcomp::DEFAULT_REGISTER_COUNT;
+pub use self::wristcomp::ExecError;
+pub use self::wristcomp::ExecOutcome;
 pub use self::wristcomp::Instruction;
 pub use self::wristcomp::Operation;
-pub use self::wristcomp::WristComputer;
\ No newline at end of file
+pub use self::wristcomp::OverflowMode;
+pub use self::optimise::MacroOp;
+pub use self::optimise::optimise_program;
+pub use self::optimise::OptimisedInstruction;
+pub use self::optimise::OptimisedProgram;
+pub use self::profile::Profile;
+pub use self::program::ParseError;
+pub use self::program::ParseErrorKind;
+pub use self::program::Program;
+pub use self::registers::MAX_REGISTERS;
+pub use self::registers::RegisterFile;
+pub use self::snapshot::Snapshot;
+pub use self::symbolic::BinaryOp;
+pub use self::symbolic::BlockEffect;
+pub use self::symbolic::execute_path;
+pub use self::symbolic::SymbolicExit;
+pub use self::symbolic::SymbolicPath;
+pub use self::symbolic::SymbolicState;
+pub use self::symbolic::SymExpr;
+pub use self::trace::TraceFormat;
+pub use self::trace::TraceWriter;
+pub use self::wristcomp::StepResult;
+pub use self::wristcomp::StopReason;
+pub use self::wristcomp::WatchCondition;
+pub use self::wristcomp::Watchpoint;
+pub use self::wristcomp::WordWidth;
+pub use self::wristcomp::WristComputer;
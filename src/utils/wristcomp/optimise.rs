@@ -0,0 +1,427 @@
+use std::cmp;
+
+use super::cfg::get_all_jump_targets;
+use super::cfg::JumpTargets;
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+
+/// Number of instructions in the nested multiply-compare loop replaced by MacroOp::DivisorSum.
+const DIVISOR_SUM_LEN: usize = 14;
+/// Number of instructions in the counting loop replaced by MacroOp::DivideCount.
+const DIVIDE_COUNT_LEN: usize = 8;
+
+/// Native operations replacing entire loops recognised in wrist computer programs.
+///
+/// Each macro-op leaves the registers in exactly the same state as executing the loop it replaces
+/// one instruction at a time.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MacroOp {
+    /// Nested loop from AoC 2018 Day 19, adding to the accumulator every value of the factor
+    /// register (counted up to the target) that divides the target.
+    DivisorSum { factor: usize, counter: usize, temp: usize, target: usize, acc: usize, exit: usize },
+    /// Counting loop from AoC 2018 Day 21, finding the quotient of the dividend by the divisor by
+    /// incrementing the counter until (counter + 1) * divisor exceeds the dividend.
+    DivideCount { counter: usize, temp: usize, dividend: usize, divisor: usize, exit: usize },
+}
+
+impl MacroOp {
+    /// Applies the macro-op to the registers, returning the address of the next instruction to
+    /// execute. The instruction pointer register is left with the same value the loop leaves in it.
+    ///
+    /// Returns None (leaving the registers unchanged) if any value calculated by the loop would
+    /// overflow, in which case the loop must be executed one instruction at a time instead.
+    pub fn apply(&self, registers: &mut [usize], ip_reg: usize) -> Option<usize> {
+        match *self {
+            MacroOp::DivisorSum { factor, counter, temp, target, acc, exit } => {
+                let num = registers[target];
+                let factor_start = registers[factor];
+                let factor_end = cmp::max(factor_start, num);
+                let counter_end = cmp::max(1, num);
+                // Largest product calculated is the last factor by the last counter value
+                factor_end.checked_mul(counter_end)?;
+                let sum = calculate_divisor_sum_in_range(num, factor_start, factor_end)?;
+                registers[acc] = registers[acc].checked_add(sum)?;
+                registers[factor] = factor_end + 1;
+                registers[counter] = counter_end + 1;
+                registers[temp] = 1;
+                registers[ip_reg] = exit;
+                return Some(exit + 1);
+            },
+            MacroOp::DivideCount { counter, temp, dividend, divisor, exit } => {
+                let quotient = cmp::max(registers[counter], registers[dividend] / divisor);
+                // Loop ends by calculating the product of the next counter value and the divisor
+                quotient.checked_add(1)?.checked_mul(divisor)?;
+                registers[counter] = quotient;
+                registers[temp] = 1;
+                registers[ip_reg] = exit;
+                return Some(exit + 1);
+            }
+        }
+    }
+}
+
+/// Calculates the sum of the divisors of num that lie between low and high (inclusive). Returns
+/// None if the sum overflows.
+fn calculate_divisor_sum_in_range(num: usize, low: usize, high: usize) -> Option<usize> {
+    let mut sum: usize = 0;
+    let mut div = 1;
+    while div <= num / div {
+        if num.is_multiple_of(div) {
+            let pair = num / div;
+            if div >= low && div <= high {
+                sum = sum.checked_add(div)?;
+            }
+            if pair != div && pair >= low && pair <= high {
+                sum = sum.checked_add(pair)?;
+            }
+        }
+        div += 1;
+    }
+    return Some(sum);
+}
+
+/// Instruction in an optimised program. Macro-ops are kept along with the first instruction of
+/// the loop they replace, which is executed instead if the macro-op cannot be applied.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OptimisedInstruction {
+    Native(Instruction),
+    Macro(MacroOp, Instruction),
+}
+
+/// A wrist computer program with recognised loops replaced by macro-ops.
+///
+/// Only the first instruction of each loop is replaced, so all instruction addresses are unchanged
+/// from the original program. Must be executed by a wrist computer bound to the same instruction
+/// pointer register.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OptimisedProgram {
+    ip_reg: Option<usize>,
+    instructions: Vec<OptimisedInstruction>,
+}
+
+impl OptimisedProgram {
+    pub fn get_ip_reg(&self) -> Option<usize> {
+        return self.ip_reg;
+    }
+
+    pub fn get_instructions(&self) -> &[OptimisedInstruction] {
+        return &self.instructions;
+    }
+
+    /// Gets the number of macro-ops inserted into the program.
+    pub fn get_macro_count(&self) -> usize {
+        return self.instructions.iter().filter(|x| matches!(x, OptimisedInstruction::Macro(..))).count();
+    }
+}
+
+/// Replaces known loop idioms in the program with macro-ops.
+///
+/// A loop is only replaced if it can be proven that no instruction outside of the loop can move
+/// the instruction pointer anywhere into the loop other than its first instruction.
+pub fn optimise_program(program: &[Instruction], ip_reg: Option<usize>) -> OptimisedProgram {
+    let mut instructions = program.iter().map(|x| OptimisedInstruction::Native(*x))
+        .collect::<Vec<OptimisedInstruction>>();
+    if let Some(ip) = ip_reg {
+        let mut head = 1;
+        while head < program.len() {
+            let found = match match_divisor_sum(program, head, ip) {
+                Some(macro_op) => Some((macro_op, DIVISOR_SUM_LEN)),
+                None => match_divide_count(program, head, ip).map(|x| (x, DIVIDE_COUNT_LEN)),
+            };
+            match found {
+                Some((macro_op, len)) if is_entered_only_at_head(program, ip_reg, head, head + len) => {
+                    instructions[head] = OptimisedInstruction::Macro(macro_op, program[head]);
+                    head += len;
+                },
+                _ => head += 1,
+            }
+        }
+    }
+    return OptimisedProgram {
+        ip_reg: ip_reg,
+        instructions: instructions,
+    };
+}
+
+/// Checks that control can only enter the region of the program from "head" (inclusive) to "end"
+/// (exclusive) through the head instruction.
+fn is_entered_only_at_head(program: &[Instruction], ip_reg: Option<usize>, head: usize, end: usize) -> bool {
+    let is_outside_body = |target: usize| target <= head || target >= end;
+    let jumps = get_all_jump_targets(program, ip_reg);
+    for addr in (0..head).chain(end..program.len()) {
+        let is_safe = match &jumps[addr] {
+            JumpTargets::Next => is_outside_body(addr + 1),
+            JumpTargets::Static(targets) => targets.iter().all(|x| is_outside_body(*x)),
+            JumpTargets::AtLeast(target) => *target >= end,
+            JumpTargets::Unknown => false,
+        };
+        if !is_safe {
+            return false;
+        }
+    }
+    return true;
+}
+
+/// Gets the values of the instruction if it performs the given operation.
+fn get_values_if(instruction: &Instruction, op: Operation) -> Option<(usize, usize, usize)> {
+    if instruction.get_operation() != op {
+        return None;
+    }
+    return Some(instruction.get_values());
+}
+
+/// Gets the other operand of a commutative operation if one of the operands is the known value.
+fn get_other_operand(operands: (usize, usize), known: usize) -> Option<usize> {
+    if operands.0 == known {
+        return Some(operands.1);
+    } else if operands.1 == known {
+        return Some(operands.0);
+    }
+    return None;
+}
+
+/// Checks if the instruction is "addr reg ip ip" (in either operand order), skipping the next
+/// instruction if the register holds 1.
+fn is_skip_if(instruction: &Instruction, reg: usize, ip: usize) -> bool {
+    match get_values_if(instruction, Operation::AddReg) {
+        Some((a, b, c)) => return c == ip && get_other_operand((a, b), ip) == Some(reg),
+        None => return false,
+    }
+}
+
+/// Checks if the instruction is "addi reg val reg".
+fn is_increment(instruction: &Instruction, reg: usize, val: usize) -> bool {
+    return get_values_if(instruction, Operation::AddImm) == Some((reg, val, reg));
+}
+
+/// Gets the address jumped to by a "seti X _ ip" instruction.
+fn get_goto_target(instruction: &Instruction, ip: usize) -> Option<usize> {
+    let (a, _b, c) = get_values_if(instruction, Operation::SetImm)?;
+    if c != ip {
+        return None;
+    }
+    return a.checked_add(1);
+}
+
+fn are_distinct(regs: &[usize]) -> bool {
+    for i in 0..regs.len() {
+        if regs[i + 1..].contains(&regs[i]) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/// Matches the nested multiply-compare loop summing divisors, in the form:
+///
+/// ```text
+/// head+0:  seti 1 _ counter
+/// head+1:  mulr factor counter temp
+/// head+2:  eqrr temp target temp
+/// head+3:  addr temp ip ip
+/// head+4:  addi ip 1 ip
+/// head+5:  addr factor acc acc
+/// head+6:  addi counter 1 counter
+/// head+7:  gtrr counter target temp
+/// head+8:  addr ip temp ip
+/// head+9:  seti head _ ip
+/// head+10: addi factor 1 factor
+/// head+11: gtrr factor target temp
+/// head+12: addr temp ip ip
+/// head+13: seti head-1 _ ip
+/// ```
+///
+/// Operands of commutative operations may be in either order.
+fn match_divisor_sum(program: &[Instruction], head: usize, ip: usize) -> Option<MacroOp> {
+    if head == 0 || head + DIVISOR_SUM_LEN > program.len() {
+        return None;
+    }
+    let code = &program[head..head + DIVISOR_SUM_LEN];
+    let (init, _, counter) = get_values_if(&code[0], Operation::SetImm)?;
+    let (a, b, temp) = get_values_if(&code[1], Operation::MulReg)?;
+    let factor = get_other_operand((a, b), counter)?;
+    let (a, b, c) = get_values_if(&code[2], Operation::EqRegReg)?;
+    let target = get_other_operand((a, b), temp)?;
+    let (a, b, acc) = get_values_if(&code[5], Operation::AddReg)?;
+    let is_match = init == 1 && c == temp
+        && is_skip_if(&code[3], temp, ip)
+        && is_increment(&code[4], ip, 1)
+        && get_other_operand((a, b), acc) == Some(factor)
+        && is_increment(&code[6], counter, 1)
+        && get_values_if(&code[7], Operation::GtRegReg) == Some((counter, target, temp))
+        && is_skip_if(&code[8], temp, ip)
+        && get_goto_target(&code[9], ip) == Some(head + 1)
+        && is_increment(&code[10], factor, 1)
+        && get_values_if(&code[11], Operation::GtRegReg) == Some((factor, target, temp))
+        && is_skip_if(&code[12], temp, ip)
+        && get_goto_target(&code[13], ip) == Some(head)
+        && are_distinct(&[ip, factor, counter, temp, target, acc]);
+    if !is_match {
+        return None;
+    }
+    return Some(MacroOp::DivisorSum {
+        factor: factor,
+        counter: counter,
+        temp: temp,
+        target: target,
+        acc: acc,
+        exit: head + DIVISOR_SUM_LEN - 1,
+    });
+}
+
+/// Matches the counting loop dividing by a constant, in the form:
+///
+/// ```text
+/// head+0: addi counter 1 temp
+/// head+1: muli temp divisor temp
+/// head+2: gtrr temp dividend temp
+/// head+3: addr temp ip ip
+/// head+4: addi ip 1 ip
+/// head+5: seti exit _ ip
+/// head+6: addi counter 1 counter
+/// head+7: seti head-1 _ ip
+/// ```
+///
+/// The divisor must not be 0, as the loop would never end.
+fn match_divide_count(program: &[Instruction], head: usize, ip: usize) -> Option<MacroOp> {
+    if head == 0 || head + DIVIDE_COUNT_LEN > program.len() {
+        return None;
+    }
+    let code = &program[head..head + DIVIDE_COUNT_LEN];
+    let (counter, init, temp) = get_values_if(&code[0], Operation::AddImm)?;
+    let (_, divisor, _) = get_values_if(&code[1], Operation::MulImm)?;
+    let (_, dividend, _) = get_values_if(&code[2], Operation::GtRegReg)?;
+    let exit_target = get_goto_target(&code[5], ip)?;
+    let is_match = init == 1 && divisor > 0
+        && get_values_if(&code[1], Operation::MulImm) == Some((temp, divisor, temp))
+        && get_values_if(&code[2], Operation::GtRegReg) == Some((temp, dividend, temp))
+        && is_skip_if(&code[3], temp, ip)
+        && is_increment(&code[4], ip, 1)
+        && (exit_target <= head || exit_target >= head + DIVIDE_COUNT_LEN)
+        && is_increment(&code[6], counter, 1)
+        && get_goto_target(&code[7], ip) == Some(head)
+        && are_distinct(&[ip, counter, temp, dividend]);
+    if !is_match {
+        return None;
+    }
+    return Some(MacroOp::DivideCount {
+        counter: counter,
+        temp: temp,
+        dividend: dividend,
+        divisor: divisor,
+        exit: exit_target - 1,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::program::Program;
+    use super::super::wristcomp::WristComputer;
+
+    /// Day 21 style program dividing 70000 by 256 with a counting loop (at addresses 2 to 9).
+    fn divide_program() -> Program {
+        let input = "#ip 4\nseti 70000 0 1\nseti 0 0 2\naddi 2 1 3\nmuli 3 256 3\ngtrr 3 1 3\naddr 3 4 4\n\
+            addi 4 1 4\nseti 9 0 4\naddi 2 1 2\nseti 1 0 4\nsetr 2 0 0";
+        return input.parse::<Program>().unwrap();
+    }
+
+    #[test]
+    fn test_optimise_d19_matches_naive() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let optimised = optimise_program(program.get_instructions(), program.get_ip_reg());
+        assert_eq!(1, optimised.get_macro_count());
+        let mut naive_comp = WristComputer::new(program.get_ip_reg());
+        naive_comp.execute_program(program.get_instructions());
+        let mut opt_comp = WristComputer::new(program.get_ip_reg());
+        opt_comp.execute_optimised_program(&optimised);
+        assert_eq!(naive_comp.get_registers(), opt_comp.get_registers());
+        // Part 2 input runs in a handful of steps
+        let mut opt_comp = WristComputer::new(program.get_ip_reg());
+        opt_comp.update_register_zero(1);
+        opt_comp.execute_optimised_program(&optimised);
+        assert_eq!(18964204, opt_comp.get_registers()[0]);
+        assert!(opt_comp.get_steps() < 100);
+    }
+
+    #[test]
+    fn test_optimise_divide_count_matches_naive() {
+        let program = divide_program();
+        let optimised = optimise_program(program.get_instructions(), program.get_ip_reg());
+        let macro_op = MacroOp::DivideCount { counter: 2, temp: 3, dividend: 1, divisor: 256, exit: 9 };
+        assert_eq!(OptimisedInstruction::Macro(macro_op, program.get_instructions()[2]), optimised.get_instructions()[2]);
+        let mut naive_comp = WristComputer::new(program.get_ip_reg());
+        naive_comp.execute_program(program.get_instructions());
+        let mut opt_comp = WristComputer::new(program.get_ip_reg());
+        opt_comp.execute_optimised_program(&optimised);
+        assert_eq!(273, opt_comp.get_registers()[0]);
+        assert_eq!(naive_comp.get_registers(), opt_comp.get_registers());
+    }
+
+    #[test]
+    fn test_optimise_rejects_loop_entered_in_body() {
+        let mut instructions = divide_program().get_instructions().to_vec();
+        // Jump from the end of the program to the middle of the loop
+        instructions.push(Instruction::new(Operation::SetImm, (2, 0, 4)));
+        let optimised = optimise_program(&instructions, Some(4));
+        assert_eq!(0, optimised.get_macro_count());
+    }
+
+    #[test]
+    fn test_optimise_rejects_loop_after_bypassed_skip() {
+        // Skip at address 3 leads to address 4 or the loop head at 5, as long as its comparison runs
+        let input = "#ip 4\nseti 70000 0 1\nseti 0 0 2\neqri 2 5 3\naddr 3 4 4\nseti 0 0 0\naddi 2 1 3\n\
+            muli 3 256 3\ngtrr 3 1 3\naddr 3 4 4\naddi 4 1 4\nseti 12 0 4\naddi 2 1 2\nseti 4 0 4\nsetr 2 0 0";
+        let program = input.parse::<Program>().unwrap();
+        assert_eq!(1, optimise_program(program.get_instructions(), program.get_ip_reg()).get_macro_count());
+        // Jumping straight to the skip could move the instruction pointer into the loop body
+        let mut instructions = program.get_instructions().to_vec();
+        instructions.push(Instruction::new(Operation::SetImm, (2, 0, 4)));
+        assert_eq!(0, optimise_program(&instructions, Some(4)).get_macro_count());
+    }
+
+    /// Program summing the divisors of a target and starting factor both set to the given value,
+    /// with a divisor sum loop at addresses 2 to 15.
+    fn divisor_sum_program(start: usize) -> Program {
+        let input = format!("#ip 5\nseti {} 0 4\nsetr 4 0 1\nseti 1 0 2\nmulr 1 2 3\neqrr 3 4 3\naddr 3 5 5\n\
+            addi 5 1 5\naddr 1 0 0\naddi 2 1 2\ngtrr 2 4 3\naddr 5 3 5\nseti 2 0 5\naddi 1 1 1\ngtrr 1 4 3\n\
+            addr 3 5 5\nseti 1 0 5", start);
+        return input.parse::<Program>().unwrap();
+    }
+
+    #[test]
+    fn test_macro_op_overflow_not_applied() {
+        let program = divisor_sum_program(usize::MAX / 2);
+        let optimised = optimise_program(program.get_instructions(), program.get_ip_reg());
+        let macro_op = match optimised.get_instructions()[2] {
+            OptimisedInstruction::Macro(macro_op, _) => macro_op,
+            _ => panic!("divisor sum loop not replaced"),
+        };
+        // Loop multiplies the factor by counter values up to the target
+        let mut registers = vec![0, usize::MAX / 2, 0, 0, usize::MAX / 2, 2];
+        assert_eq!(None, macro_op.apply(&mut registers, 5));
+        assert_eq!(vec![0, usize::MAX / 2, 0, 0, usize::MAX / 2, 2], registers);
+        // Sum of divisors 1, 2, 3 and 6 added to the accumulator
+        let mut registers = vec![usize::MAX - 12, 1, 0, 0, 6, 2];
+        assert_eq!(Some(16), macro_op.apply(&mut registers, 5));
+        assert_eq!(usize::MAX, registers[0]);
+        let mut registers = vec![usize::MAX - 11, 1, 0, 0, 6, 2];
+        assert_eq!(None, macro_op.apply(&mut registers, 5));
+        let macro_op = MacroOp::DivideCount { counter: 2, temp: 3, dividend: 1, divisor: 256, exit: 9 };
+        let mut registers = vec![0, usize::MAX, 0, 0, 2];
+        assert_eq!(None, macro_op.apply(&mut registers, 4));
+        assert_eq!(vec![0, usize::MAX, 0, 0, 2], registers);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn test_optimised_overflow_matches_naive() {
+        // Naive execution overflows multiplying the factor by the third counter value
+        let program = divisor_sum_program(usize::MAX / 2);
+        let mut naive_comp = WristComputer::new(program.get_ip_reg());
+        assert!(naive_comp.try_execute_program(program.get_instructions(), None).is_err());
+        let optimised = optimise_program(program.get_instructions(), program.get_ip_reg());
+        let mut opt_comp = WristComputer::new(program.get_ip_reg());
+        opt_comp.execute_optimised_program(&optimised);
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+/// Execution counts gathered by the wrist computer while profiling is enabled.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Profile {
+    hits: Vec<u64>,
+    transitions: HashMap<(usize, usize), u64>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            hits: vec![],
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Records the execution of the instruction at the "from" address, with control moving to the
+    /// "to" address afterwards.
+    pub fn record(&mut self, from: usize, to: usize) {
+        if from >= self.hits.len() {
+            self.hits.resize(from + 1, 0);
+        }
+        self.hits[from] += 1;
+        *self.transitions.entry((from, to)).or_insert(0) += 1;
+    }
+
+    /// Gets the number of times the instruction at the given address was executed.
+    pub fn get_hits(&self, addr: usize) -> u64 {
+        return *self.hits.get(addr).unwrap_or(&0);
+    }
+
+    /// Gets the number of times control moved from one address to the other.
+    pub fn get_transition_count(&self, from: usize, to: usize) -> u64 {
+        return *self.transitions.get(&(from, to)).unwrap_or(&0);
+    }
+
+    pub fn get_transitions(&self) -> &HashMap<(usize, usize), u64> {
+        return &self.transitions;
+    }
+
+    /// Gets the total number of instructions executed.
+    pub fn get_total_steps(&self) -> u64 {
+        return self.hits.iter().sum();
+    }
+
+    /// Gets up to n of the most executed instruction addresses along with their hit counts.
+    ///
+    /// Addresses with the same hit count are sorted in ascending order.
+    pub fn get_hottest_addresses(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut output = self.hits.iter().copied().enumerate().filter(|(_addr, hits)| *hits > 0)
+            .collect::<Vec<(usize, u64)>>();
+        output.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        output.truncate(n);
+        return output;
+    }
+
+    /// Gets the backward (or self) transitions taken, which close the loops in the program, along
+    /// with their counts. Sorted by most taken first.
+    pub fn get_back_edges(&self) -> Vec<((usize, usize), u64)> {
+        let mut output = self.transitions.iter().filter(|((from, to), _count)| to <= from)
+            .map(|(edge, count)| (*edge, *count))
+            .collect::<Vec<((usize, usize), u64)>>();
+        output.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        return output;
+    }
+}
@@ -0,0 +1,239 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+
+/// Character starting a comment that runs to the end of the line.
+const COMMENT_CHAR: char = ';';
+
+/// Kinds of errors that can occur when parsing a wrist computer program.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseErrorKind {
+    UnknownMnemonic(String),
+    MissingOperand,
+    InvalidOperand(String),
+    UnexpectedToken(String),
+    InvalidIpBinding(String),
+    DuplicateIpBinding,
+    UnknownField(String),
+    DuplicateField(String),
+    MissingField(String),
+}
+
+/// Error raised when parsing a wrist computer program or snapshot, located by 1-indexed line and column.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, kind: ParseErrorKind) -> Self {
+        Self {
+            line: line,
+            column: column,
+            kind: kind,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnknownMnemonic(s) => return write!(f, "unknown mnemonic \"{}\"", s),
+            ParseErrorKind::MissingOperand => return write!(f, "missing operand"),
+            ParseErrorKind::InvalidOperand(s) => return write!(f, "invalid operand \"{}\"", s),
+            ParseErrorKind::UnexpectedToken(s) => return write!(f, "unexpected token \"{}\"", s),
+            ParseErrorKind::InvalidIpBinding(s) => {
+                return write!(f, "invalid instruction pointer register \"{}\"", s);
+            },
+            ParseErrorKind::DuplicateIpBinding => {
+                return write!(f, "instruction pointer register already bound");
+            },
+            ParseErrorKind::UnknownField(s) => return write!(f, "unknown field \"{}\"", s),
+            ParseErrorKind::DuplicateField(s) => return write!(f, "field \"{}\" already given", s),
+            ParseErrorKind::MissingField(s) => return write!(f, "missing field \"{}\"", s),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// A wrist computer program, along with the register the instruction pointer is bound to (if any).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Program {
+    ip_reg: Option<usize>,
+    instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn new(ip_reg: Option<usize>, instructions: Vec<Instruction>) -> Self {
+        Self {
+            ip_reg: ip_reg,
+            instructions: instructions,
+        }
+    }
+
+    pub fn get_ip_reg(&self) -> Option<usize> {
+        return self.ip_reg;
+    }
+
+    pub fn get_instructions(&self) -> &[Instruction] {
+        return &self.instructions;
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    /// Parses a program made up of an optional "#ip N" binding and one "opname a b c" instruction
+    /// per line. Blank lines and comments starting with ';' are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ip_reg: Option<usize> = None;
+        let mut instructions = Vec::<Instruction>::new();
+        for (i, line) in s.lines().enumerate() {
+            let line_num = i + 1;
+            let tokens = tokenise_line(line);
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens[0].1 == "#ip" {
+                if ip_reg.is_some() {
+                    return Err(ParseError::new(line_num, tokens[0].0, ParseErrorKind::DuplicateIpBinding));
+                }
+                ip_reg = Some(parse_ip_binding(&tokens, line_num, line)?);
+            } else {
+                instructions.push(parse_instruction(&tokens, line_num, line)?);
+            }
+        }
+        return Ok(Program::new(ip_reg, instructions));
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    /// Parses a single instruction in the form "opname a b c".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenise_line(s);
+        return parse_instruction(&tokens, 1, s);
+    }
+}
+
+/// Splits the line into whitespace-separated tokens along with their 1-indexed starting columns,
+/// stopping at the start of any comment.
+pub(super) fn tokenise_line(line: &str) -> Vec<(usize, &str)> {
+    let line = match line.find(COMMENT_CHAR) {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    let mut tokens = Vec::<(usize, &str)>::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in line.char_indices() {
+        if c.is_whitespace() {
+            if let Some(j) = start {
+                tokens.push((j, &line[j..i]));
+                start = None;
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(j) = start {
+        tokens.push((j, &line[j..]));
+    }
+    // Convert byte offsets into 1-indexed character columns
+    return tokens.iter().map(|(i, tok)| (line[..*i].chars().count() + 1, *tok)).collect();
+}
+
+/// Gets the 1-indexed column just past the end of the line content, ignoring any comment.
+fn get_end_column(line: &str) -> usize {
+    let line = match line.find(COMMENT_CHAR) {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    return line.trim_end().chars().count() + 1;
+}
+
+fn parse_ip_binding(tokens: &[(usize, &str)], line_num: usize, line: &str) -> Result<usize, ParseError> {
+    if tokens.len() < 2 {
+        return Err(ParseError::new(line_num, get_end_column(line), ParseErrorKind::MissingOperand));
+    }
+    if tokens.len() > 2 {
+        let kind = ParseErrorKind::UnexpectedToken(tokens[2].1.to_string());
+        return Err(ParseError::new(line_num, tokens[2].0, kind));
+    }
+    match tokens[1].1.parse::<usize>() {
+        Ok(reg) => return Ok(reg),
+        Err(_) => {
+            let kind = ParseErrorKind::InvalidIpBinding(tokens[1].1.to_string());
+            return Err(ParseError::new(line_num, tokens[1].0, kind));
+        }
+    }
+}
+
+fn parse_instruction(tokens: &[(usize, &str)], line_num: usize, line: &str) -> Result<Instruction, ParseError> {
+    if tokens.is_empty() {
+        return Err(ParseError::new(line_num, get_end_column(line), ParseErrorKind::MissingOperand));
+    }
+    let op = match Operation::from_string(tokens[0].1) {
+        Some(op) => op,
+        None => {
+            let kind = ParseErrorKind::UnknownMnemonic(tokens[0].1.to_string());
+            return Err(ParseError::new(line_num, tokens[0].0, kind));
+        }
+    };
+    let mut values = Vec::<usize>::new();
+    for i in 1..=3 {
+        if i >= tokens.len() {
+            return Err(ParseError::new(line_num, get_end_column(line), ParseErrorKind::MissingOperand));
+        }
+        match tokens[i].1.parse::<usize>() {
+            Ok(val) => values.push(val),
+            Err(_) => {
+                let kind = ParseErrorKind::InvalidOperand(tokens[i].1.to_string());
+                return Err(ParseError::new(line_num, tokens[i].0, kind));
+            }
+        }
+    }
+    if tokens.len() > 4 {
+        let kind = ParseErrorKind::UnexpectedToken(tokens[4].1.to_string());
+        return Err(ParseError::new(line_num, tokens[4].0, kind));
+    }
+    return Ok(Instruction::new(op, (values[0], values[1], values[2])));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_parse() {
+        let input = "; Example from AoC 2018 Day 19\n#ip 0\nseti 5 0 1\n\n  seti 6 0 2 ; r2 = 6\naddi 0 1 0";
+        let program = input.parse::<Program>().unwrap();
+        assert_eq!(Some(0), program.get_ip_reg());
+        let expected = vec![
+            Instruction::new(Operation::SetImm, (5, 0, 1)),
+            Instruction::new(Operation::SetImm, (6, 0, 2)),
+            Instruction::new(Operation::AddImm, (0, 1, 0)),
+        ];
+        assert_eq!(expected, program.get_instructions());
+    }
+
+    #[test]
+    fn test_program_parse_errors() {
+        let err = "#ip 0\nseti 5 0 1\nfoo 1 2 3".parse::<Program>().unwrap_err();
+        assert_eq!(ParseError::new(3, 1, ParseErrorKind::UnknownMnemonic(String::from("foo"))), err);
+        let err = "addi 1 2 ; 3".parse::<Program>().unwrap_err();
+        assert_eq!(ParseError::new(1, 9, ParseErrorKind::MissingOperand), err);
+        let err = "addi 1 x 3".parse::<Instruction>().unwrap_err();
+        assert_eq!(ParseError::new(1, 8, ParseErrorKind::InvalidOperand(String::from("x"))), err);
+        let err = "#ip 1\naddi 1 2 3\n  #ip 2".parse::<Program>().unwrap_err();
+        assert_eq!(ParseError::new(3, 3, ParseErrorKind::DuplicateIpBinding), err);
+        assert_eq!("line 3, column 3: instruction pointer register already bound", err.to_string());
+    }
+}
@@ -0,0 +1,67 @@
+use std::ops::Index;
+use std::ops::IndexMut;
+
+/// Maximum number of registers that a wrist computer can hold.
+pub const MAX_REGISTERS: usize = 16;
+
+/// Fixed-capacity register file for the wrist computer, allowing instructions to update registers
+/// in place without any allocation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RegisterFile {
+    values: [usize; MAX_REGISTERS],
+    len: usize,
+}
+
+impl RegisterFile {
+    /// Creates a new register file with the given number of registers, all initialised to 0.
+    pub fn new(len: usize) -> Self {
+        if len > MAX_REGISTERS {
+            panic!("WristComputer - cannot have more than {} registers.", MAX_REGISTERS);
+        }
+        Self {
+            values: [0; MAX_REGISTERS],
+            len: len,
+        }
+    }
+
+    /// Creates a new register file holding the given register values.
+    pub fn from_slice(values: &[usize]) -> Self {
+        let mut registers = RegisterFile::new(values.len());
+        registers.values[..values.len()].copy_from_slice(values);
+        return registers;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn as_slice(&self) -> &[usize] {
+        return &self.values[..self.len];
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [usize] {
+        return &mut self.values[..self.len];
+    }
+
+    pub fn to_vec(&self) -> Vec<usize> {
+        return self.as_slice().to_vec();
+    }
+}
+
+impl Index<usize> for RegisterFile {
+    type Output = usize;
+
+    fn index(&self, reg: usize) -> &usize {
+        return &self.as_slice()[reg];
+    }
+}
+
+impl IndexMut<usize> for RegisterFile {
+    fn index_mut(&mut self, reg: usize) -> &mut usize {
+        return &mut self.as_mut_slice()[reg];
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::program::tokenise_line;
+use super::program::ParseError;
+use super::program::ParseErrorKind;
+use super::registers::MAX_REGISTERS;
+
+/// Captured state of a wrist computer part way through running a program, which can be used to
+/// resume the run later or to fork it with modified registers.
+///
+/// Snapshots round-trip through a simple text format with one field per line:
+///
+/// ```text
+/// #ip 4
+/// ip 10
+/// steps 19
+/// registers 0 2 0 1 9 964
+/// ```
+///
+/// The "#ip" line is omitted if the instruction pointer is not bound to a register. Blank lines
+/// and comments starting with ';' are ignored.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Snapshot {
+    registers: Vec<usize>,
+    ip_reg: Option<usize>,
+    ip_val: usize,
+    steps: u64,
+}
+
+impl Snapshot {
+    pub fn new(registers: Vec<usize>, ip_reg: Option<usize>, ip_val: usize, steps: u64) -> Self {
+        if registers.len() > MAX_REGISTERS {
+            panic!("Snapshot - cannot have more than {} registers.", MAX_REGISTERS);
+        }
+        if let Some(reg) = ip_reg {
+            if reg >= registers.len() {
+                panic!("Snapshot - instruction pointer register {} out of range.", reg);
+            }
+        }
+        Self {
+            registers: registers,
+            ip_reg: ip_reg,
+            ip_val: ip_val,
+            steps: steps,
+        }
+    }
+
+    pub fn get_registers(&self) -> &[usize] {
+        return &self.registers;
+    }
+
+    /// Sets the value held in the given register, e.g. to fork a run with a modified register.
+    pub fn set_register(&mut self, reg: usize, val: usize) {
+        self.registers[reg] = val;
+    }
+
+    pub fn get_ip_reg(&self) -> Option<usize> {
+        return self.ip_reg;
+    }
+
+    pub fn get_ip(&self) -> usize {
+        return self.ip_val;
+    }
+
+    pub fn get_steps(&self) -> u64 {
+        return self.steps;
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(reg) = self.ip_reg {
+            writeln!(f, "#ip {}", reg)?;
+        }
+        writeln!(f, "ip {}", self.ip_val)?;
+        writeln!(f, "steps {}", self.steps)?;
+        let registers = self.registers.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        return writeln!(f, "registers {}", registers.join(" "));
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ip_reg: Option<(usize, usize, usize)> = None; // register, line and column
+        let mut ip_val: Option<usize> = None;
+        let mut steps: Option<u64> = None;
+        let mut registers: Option<Vec<usize>> = None;
+        let mut last_line = 0;
+        for (i, line) in s.lines().enumerate() {
+            let line_num = i + 1;
+            last_line = line_num;
+            let tokens = tokenise_line(line);
+            if tokens.is_empty() {
+                continue;
+            }
+            let (column, field) = tokens[0];
+            let is_duplicate = match field {
+                "#ip" => ip_reg.is_some(),
+                "ip" => ip_val.is_some(),
+                "steps" => steps.is_some(),
+                "registers" => registers.is_some(),
+                _ => return Err(ParseError::new(line_num, column, ParseErrorKind::UnknownField(field.to_string()))),
+            };
+            if is_duplicate {
+                return Err(ParseError::new(line_num, column, ParseErrorKind::DuplicateField(field.to_string())));
+            }
+            if field == "registers" {
+                if tokens.len() - 1 > MAX_REGISTERS {
+                    let kind = ParseErrorKind::UnexpectedToken(tokens[MAX_REGISTERS + 1].1.to_string());
+                    return Err(ParseError::new(line_num, tokens[MAX_REGISTERS + 1].0, kind));
+                }
+                let mut values = Vec::<usize>::new();
+                for (column, token) in tokens.iter().skip(1) {
+                    match token.parse::<usize>() {
+                        Ok(val) => values.push(val),
+                        Err(_) => {
+                            let kind = ParseErrorKind::InvalidOperand(token.to_string());
+                            return Err(ParseError::new(line_num, *column, kind));
+                        }
+                    }
+                }
+                registers = Some(values);
+                continue;
+            }
+            // Remaining fields hold a single value
+            if tokens.len() < 2 {
+                let kind = ParseErrorKind::MissingOperand;
+                return Err(ParseError::new(line_num, column + field.chars().count(), kind));
+            }
+            if tokens.len() > 2 {
+                let kind = ParseErrorKind::UnexpectedToken(tokens[2].1.to_string());
+                return Err(ParseError::new(line_num, tokens[2].0, kind));
+            }
+            let (value_column, value) = tokens[1];
+            let val = match value.parse::<u64>() {
+                Ok(val) => val,
+                Err(_) => {
+                    let kind = match field {
+                        "#ip" => ParseErrorKind::InvalidIpBinding(value.to_string()),
+                        _ => ParseErrorKind::InvalidOperand(value.to_string()),
+                    };
+                    return Err(ParseError::new(line_num, value_column, kind));
+                }
+            };
+            match field {
+                "#ip" => ip_reg = Some((val as usize, line_num, value_column)),
+                "ip" => ip_val = Some(val as usize),
+                _ => steps = Some(val),
+            }
+        }
+        // Check all required fields are present and the instruction pointer binding is valid
+        let missing = |field: &str| ParseError::new(last_line + 1, 1, ParseErrorKind::MissingField(field.to_string()));
+        let ip_val = ip_val.ok_or_else(|| missing("ip"))?;
+        let steps = steps.ok_or_else(|| missing("steps"))?;
+        let registers = registers.ok_or_else(|| missing("registers"))?;
+        if let Some((reg, line_num, column)) = ip_reg {
+            if reg >= registers.len() {
+                let kind = ParseErrorKind::InvalidIpBinding(reg.to_string());
+                return Err(ParseError::new(line_num, column, kind));
+            }
+        }
+        return Ok(Snapshot::new(registers, ip_reg.map(|x| x.0), ip_val, steps));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot = Snapshot::new(vec![0, 2, 0, 1, 9, 964], Some(4), 10, 19);
+        let text = snapshot.to_string();
+        assert_eq!("#ip 4\nip 10\nsteps 19\nregisters 0 2 0 1 9 964\n", text);
+        assert_eq!(snapshot, text.parse::<Snapshot>().unwrap());
+        let snapshot = Snapshot::new(vec![1, 2, 3, 4], None, 0, 0);
+        assert_eq!(snapshot, snapshot.to_string().parse::<Snapshot>().unwrap());
+    }
+
+    #[test]
+    fn test_snapshot_parse_errors() {
+        let err = "ip 10\nsteps 1\nfoo 2".parse::<Snapshot>().unwrap_err();
+        assert_eq!(ParseError::new(3, 1, ParseErrorKind::UnknownField(String::from("foo"))), err);
+        let err = "ip 10\n  ip 11".parse::<Snapshot>().unwrap_err();
+        assert_eq!(ParseError::new(2, 3, ParseErrorKind::DuplicateField(String::from("ip"))), err);
+        let err = "ip 10\nregisters 1 x".parse::<Snapshot>().unwrap_err();
+        assert_eq!(ParseError::new(2, 13, ParseErrorKind::InvalidOperand(String::from("x"))), err);
+        let err = "ip 10\nregisters 1 2".parse::<Snapshot>().unwrap_err();
+        assert_eq!(ParseError::new(3, 1, ParseErrorKind::MissingField(String::from("steps"))), err);
+        let err = "#ip 2\nip 10\nsteps 5\nregisters 1 2".parse::<Snapshot>().unwrap_err();
+        assert_eq!(ParseError::new(1, 5, ParseErrorKind::InvalidIpBinding(String::from("2"))), err);
+    }
+}
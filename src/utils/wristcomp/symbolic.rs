@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::cfg::BasicBlock;
+use super::wristcomp::Instruction;
+use super::wristcomp::Operation;
+
+/// Binary operators used in symbolic expressions.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Mul,
+    And,
+    Or,
+    Gt,
+    Eq,
+}
+
+impl BinaryOp {
+    pub fn get_symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => return "+",
+            BinaryOp::Mul => return "*",
+            BinaryOp::And => return "&",
+            BinaryOp::Or => return "|",
+            BinaryOp::Gt => return ">",
+            BinaryOp::Eq => return "==",
+        }
+    }
+
+    /// Checks if the operator is a comparison, giving a result of 0 or 1.
+    pub fn is_comparison(&self) -> bool {
+        return *self == BinaryOp::Gt || *self == BinaryOp::Eq;
+    }
+
+    fn apply(&self, left: usize, right: usize) -> Option<usize> {
+        match self {
+            BinaryOp::Add => return left.checked_add(right),
+            BinaryOp::Mul => return left.checked_mul(right),
+            BinaryOp::And => return Some(left & right),
+            BinaryOp::Or => return Some(left | right),
+            BinaryOp::Gt => return Some((left > right) as usize),
+            BinaryOp::Eq => return Some((left == right) as usize),
+        }
+    }
+}
+
+/// Value of a register expressed in terms of the register values at the start of execution.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SymExpr {
+    Const(usize),
+    Initial(usize), // value held by the register at the start
+    Binary(Box<SymExpr>, BinaryOp, Box<SymExpr>),
+}
+
+impl SymExpr {
+    /// Creates the expression for the operator applied to the operands, simplifying it where
+    /// possible. Constants are folded, and moved to the right-hand side of commutative operators.
+    pub fn binary(left: SymExpr, op: BinaryOp, right: SymExpr) -> SymExpr {
+        if let (SymExpr::Const(x), SymExpr::Const(y)) = (&left, &right) {
+            if let Some(val) = op.apply(*x, *y) {
+                return SymExpr::Const(val);
+            }
+        }
+        let is_commutative = op != BinaryOp::Gt;
+        let (left, right) = match (left, right) {
+            (SymExpr::Const(x), right) if is_commutative => (right, SymExpr::Const(x)),
+            operands => operands,
+        };
+        match (op, &left, &right) {
+            (BinaryOp::Add, _, SymExpr::Const(0)) | (BinaryOp::Mul, _, SymExpr::Const(1)) |
+            (BinaryOp::Or, _, SymExpr::Const(0)) => return left,
+            (BinaryOp::Mul, _, SymExpr::Const(0)) | (BinaryOp::And, _, SymExpr::Const(0)) => {
+                return SymExpr::Const(0);
+            },
+            (BinaryOp::Eq, _, _) | (BinaryOp::And, _, _) | (BinaryOp::Or, _, _) if left == right => {
+                return if op == BinaryOp::Eq { SymExpr::Const(1) } else { left };
+            },
+            (BinaryOp::Gt, _, _) if left == right => return SymExpr::Const(0),
+            _ => (),
+        }
+        // Combine constants of repeated additions or multiplications, e.g. (r0 + 2) + 3 = r0 + 5
+        if op == BinaryOp::Add || op == BinaryOp::Mul {
+            if let (SymExpr::Binary(inner_left, inner_op, inner_right), SymExpr::Const(y)) = (&left, &right) {
+                if let (true, SymExpr::Const(x)) = (*inner_op == op, inner_right.as_ref()) {
+                    if let Some(val) = op.apply(*x, *y) {
+                        return SymExpr::binary(inner_left.as_ref().clone(), op, SymExpr::Const(val));
+                    }
+                }
+            }
+        }
+        return SymExpr::Binary(Box::new(left), op, Box::new(right));
+    }
+
+    /// Gets the value of the expression if it is a constant.
+    pub fn get_const(&self) -> Option<usize> {
+        match self {
+            SymExpr::Const(val) => return Some(*val),
+            _ => return None,
+        }
+    }
+
+    /// Checks if the expression is a comparison, and so only ever 0 or 1.
+    pub fn is_comparison(&self) -> bool {
+        match self {
+            SymExpr::Binary(_, op, _) => return op.is_comparison(),
+            _ => return false,
+        }
+    }
+
+    /// Evaluates the expression for the given initial register values. Returns None if the result
+    /// overflows.
+    pub fn evaluate(&self, initial: &[usize]) -> Option<usize> {
+        match self {
+            SymExpr::Const(val) => return Some(*val),
+            SymExpr::Initial(reg) => return Some(initial[*reg]),
+            SymExpr::Binary(left, op, right) => return op.apply(left.evaluate(initial)?, right.evaluate(initial)?),
+        }
+    }
+
+    /// Gets the registers whose initial values the expression depends on, in ascending order.
+    pub fn get_dependencies(&self) -> Vec<usize> {
+        let mut output = Vec::<usize>::new();
+        self.collect_dependencies(&mut output);
+        output.sort_unstable();
+        output.dedup();
+        return output;
+    }
+
+    fn collect_dependencies(&self, output: &mut Vec<usize>) {
+        match self {
+            SymExpr::Const(_) => (),
+            SymExpr::Initial(reg) => output.push(*reg),
+            SymExpr::Binary(left, _, right) => {
+                left.collect_dependencies(output);
+                right.collect_dependencies(output);
+            }
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymExpr::Binary(..) => return write!(f, "({})", self),
+            _ => return write!(f, "{}", self),
+        }
+    }
+}
+
+impl fmt::Display for SymExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymExpr::Const(val) => return write!(f, "{}", val),
+            SymExpr::Initial(reg) => return write!(f, "r{}", reg),
+            SymExpr::Binary(left, op, right) => {
+                left.fmt_operand(f)?;
+                write!(f, " {} ", op.get_symbol())?;
+                return right.fmt_operand(f);
+            }
+        }
+    }
+}
+
+/// Control flow out of a sequence of symbolically executed instructions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SymbolicExit {
+    Goto(usize),                                                    // jump to a known address
+    Branch { condition: SymExpr, target: usize, fallthrough: usize }, // target taken if condition holds
+    Computed(SymExpr),                                              // jump to an address computed at run time
+}
+
+impl fmt::Display for SymbolicExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicExit::Goto(target) => return write!(f, "goto {}", target),
+            SymbolicExit::Branch { condition, target, fallthrough } => {
+                return write!(f, "if {} goto {} else goto {}", condition, target, fallthrough);
+            },
+            SymbolicExit::Computed(target) => return write!(f, "goto {}", target),
+        }
+    }
+}
+
+/// Register values held by the wrist computer as expressions over the initial register values.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SymbolicState {
+    registers: Vec<SymExpr>,
+}
+
+impl SymbolicState {
+    /// Creates a state with every register holding its unknown initial value.
+    pub fn new(num_registers: usize) -> Self {
+        Self {
+            registers: (0..num_registers).map(SymExpr::Initial).collect(),
+        }
+    }
+
+    /// Creates a state with every register holding the given expression.
+    pub fn from_exprs(registers: Vec<SymExpr>) -> Self {
+        Self {
+            registers: registers,
+        }
+    }
+
+    pub fn get_registers(&self) -> &[SymExpr] {
+        return &self.registers;
+    }
+
+    pub fn get_register(&self, reg: usize) -> &SymExpr {
+        return &self.registers[reg];
+    }
+
+    pub fn set_register(&mut self, reg: usize, expr: SymExpr) {
+        self.registers[reg] = expr;
+    }
+
+    /// Executes a single instruction located at the given address.
+    fn execute(&mut self, addr: usize, instruction: &Instruction, ip_reg: Option<usize>) {
+        if let Some(ip_reg) = ip_reg {
+            self.registers[ip_reg] = SymExpr::Const(addr);
+        }
+        let op = instruction.get_operation();
+        let (a, b, c) = instruction.get_values();
+        let operand = |val: usize, is_reg: bool| {
+            if !is_reg {
+                return SymExpr::Const(val);
+            }
+            match self.registers.get(val) {
+                Some(expr) => return expr.clone(),
+                None => panic!("SymbolicState - instruction at address {} reads invalid register {}.", addr, val),
+            }
+        };
+        let left = operand(a, op.is_reg_a());
+        let right = operand(b, op.is_reg_b());
+        let result = match op {
+            Operation::SetReg | Operation::SetImm => left,
+            Operation::AddReg | Operation::AddImm => SymExpr::binary(left, BinaryOp::Add, right),
+            Operation::MulReg | Operation::MulImm => SymExpr::binary(left, BinaryOp::Mul, right),
+            Operation::BitANDReg | Operation::BitANDImm => SymExpr::binary(left, BinaryOp::And, right),
+            Operation::BitORReg | Operation::BitORImm => SymExpr::binary(left, BinaryOp::Or, right),
+            Operation::GtImmReg | Operation::GtRegImm | Operation::GtRegReg => SymExpr::binary(left, BinaryOp::Gt, right),
+            Operation::EqImmReg | Operation::EqRegImm | Operation::EqRegReg => SymExpr::binary(left, BinaryOp::Eq, right),
+        };
+        if c >= self.registers.len() {
+            panic!("SymbolicState - instruction at address {} writes invalid register {}.", addr, c);
+        }
+        self.registers[c] = result;
+    }
+
+    /// Gets the control flow after executing the instruction at the given address.
+    fn get_exit(&self, addr: usize, ip_reg: Option<usize>) -> SymbolicExit {
+        let ip_reg = match ip_reg {
+            Some(ip_reg) => ip_reg,
+            None => return SymbolicExit::Goto(addr + 1),
+        };
+        let next = SymExpr::binary(self.registers[ip_reg].clone(), BinaryOp::Add, SymExpr::Const(1));
+        match &next {
+            SymExpr::Const(target) => return SymbolicExit::Goto(*target),
+            SymExpr::Binary(condition, BinaryOp::Add, offset) if condition.is_comparison() => {
+                if let Some(offset) = offset.get_const() {
+                    return SymbolicExit::Branch {
+                        condition: condition.as_ref().clone(),
+                        target: offset + 1,
+                        fallthrough: offset,
+                    };
+                }
+            },
+            _ => (),
+        }
+        return SymbolicExit::Computed(next);
+    }
+}
+
+/// Effect of executing a basic block, as the assignments made to the registers (in terms of their
+/// values at the start of the block) and the control flow out of the block.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockEffect {
+    pub start: usize,
+    pub end: usize,
+    pub assignments: Vec<(usize, SymExpr)>, // changed registers, excluding the ip register
+    pub exit: SymbolicExit,
+}
+
+impl BlockEffect {
+    /// Symbolically executes the basic block from a state holding unknown register values.
+    pub fn new(program: &[Instruction], block: &BasicBlock, ip_reg: Option<usize>, num_registers: usize) -> Self {
+        let mut state = SymbolicState::new(num_registers);
+        for (addr, instruction) in program.iter().enumerate().take(block.end + 1).skip(block.start) {
+            state.execute(addr, instruction, ip_reg);
+        }
+        let assignments = state.registers.iter().enumerate()
+            .filter(|(reg, expr)| Some(*reg) != ip_reg && **expr != SymExpr::Initial(*reg))
+            .map(|(reg, expr)| (reg, expr.clone()))
+            .collect::<Vec<(usize, SymExpr)>>();
+        return Self {
+            start: block.start,
+            end: block.end,
+            assignments: assignments,
+            exit: state.get_exit(block.end, ip_reg),
+        };
+    }
+}
+
+impl fmt::Display for BlockEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "block {}..={}:", self.start, self.end)?;
+        for (reg, expr) in self.assignments.iter() {
+            writeln!(f, "    r{} = {}", reg, expr)?;
+        }
+        return writeln!(f, "    {}", self.exit);
+    }
+}
+
+/// Result of symbolically executing along a path through a program.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SymbolicPath {
+    pub addresses: Vec<usize>,  // addresses of the executed instructions, in order
+    pub state: SymbolicState,   // register values at the end of the path
+    pub exit: SymbolicExit,     // control flow out of the last instruction executed
+}
+
+/// Symbolically executes the program from the given address and state, following jumps to known
+/// addresses. Execution stops once a jump depends on unknown register values, the program halts,
+/// a jump leads to one of the stop addresses or an address is about to be executed a second time
+/// (i.e. a loop has been entered).
+pub fn execute_path(program: &[Instruction], ip_reg: Option<usize>, start: usize, state: SymbolicState,
+        stop_at: &[usize]) -> SymbolicPath {
+    let mut state = state;
+    let mut addresses = Vec::<usize>::new();
+    let mut visited = HashSet::<usize>::new();
+    let mut addr = start;
+    loop {
+        visited.insert(addr);
+        addresses.push(addr);
+        state.execute(addr, &program[addr], ip_reg);
+        let exit = state.get_exit(addr, ip_reg);
+        match exit {
+            SymbolicExit::Goto(target) if target < program.len() && !visited.contains(&target)
+                && !stop_at.contains(&target) => addr = target,
+            _ => {
+                return SymbolicPath {
+                    addresses: addresses,
+                    state: state,
+                    exit: exit,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cfg::ControlFlowGraph;
+    use super::super::program::Program;
+
+    #[test]
+    fn test_symbolic_block_effects_d19() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let instructions = program.get_instructions();
+        let cfg = ControlFlowGraph::new(instructions, program.get_ip_reg());
+        let effect = |start: usize| {
+            let block = cfg.get_block(cfg.get_block_node(start).unwrap()).unwrap();
+            return BlockEffect::new(instructions, block, program.get_ip_reg(), 6);
+        };
+        // Inner loop test: "mulr 3 1 2; eqrr 2 5 2; addr 2 4 4"
+        let inner = effect(3);
+        let condition = SymExpr::binary(
+            SymExpr::binary(SymExpr::Initial(3), BinaryOp::Mul, SymExpr::Initial(1)),
+            BinaryOp::Eq,
+            SymExpr::Initial(5));
+        assert_eq!(vec![(2, condition.clone())], inner.assignments);
+        assert_eq!(SymbolicExit::Branch { condition: condition, target: 7, fallthrough: 6 }, inner.exit);
+        assert_eq!("block 3..=5:\n    r2 = (r3 * r1) == r5\n    if (r3 * r1) == r5 goto 7 else goto 6\n",
+            inner.to_string());
+        // Setup ends with a jump depending on r0 - "addr 4 0 4" at address 25
+        let setup = effect(17);
+        assert_eq!(25, setup.end);
+        let target = SymExpr::binary(SymExpr::Initial(0), BinaryOp::Add, SymExpr::Const(26));
+        assert_eq!(SymbolicExit::Computed(target), setup.exit);
+    }
+
+    #[test]
+    fn test_symbolic_path_d19() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let instructions = program.get_instructions();
+        let ip_reg = program.get_ip_reg();
+        // Program starts with unknown r0 and all other registers cleared
+        let mut registers = vec![SymExpr::Const(0); 6];
+        registers[0] = SymExpr::Initial(0);
+        let setup = execute_path(instructions, ip_reg, 0, SymbolicState::from_exprs(registers), &[]);
+        assert_eq!(SymbolicExit::Computed(SymExpr::binary(SymExpr::Initial(0), BinaryOp::Add, SymExpr::Const(26))),
+            setup.exit);
+        assert_eq!(&SymExpr::Const(964), setup.state.get_register(5));
+        // Follow the jump for each value of r0 up to the start of the main loop, with the target in r5
+        let mut targets = Vec::<usize>::new();
+        for seed in 0..=1 {
+            let mut state = setup.state.clone();
+            state.set_register(0, SymExpr::Const(seed));
+            let path = execute_path(instructions, ip_reg, 26 + seed, state, &[1]);
+            assert_eq!(SymbolicExit::Goto(1), path.exit);
+            assert_eq!(&SymExpr::Const(0), path.state.get_register(0));
+            targets.push(path.state.get_register(5).get_const().unwrap());
+        }
+        assert_eq!(vec![964, 10551364], targets);
+    }
+}
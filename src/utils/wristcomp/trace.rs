@@ -0,0 +1,104 @@
+use std::io;
+use std::io::Write;
+
+use super::wristcomp::StepResult;
+
+/// Output formats for execution traces.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TraceFormat {
+    Csv,        // header line followed by one comma-separated line per step
+    JsonLines,  // one JSON object per line
+}
+
+/// Records the steps executed by the wrist computer to a writer.
+///
+/// Every step is recorded by default. Setting a sample interval of n records only every nth step,
+/// and setting a maximum record count stops recording once that many steps have been written.
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    format: TraceFormat,
+    sample_interval: u64,
+    max_records: Option<u64>,
+    record_count: u64,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        Self {
+            writer: writer,
+            format: format,
+            sample_interval: 1,
+            max_records: None,
+            record_count: 0,
+        }
+    }
+
+    /// Sets the tracer to only record steps with step number divisible by the interval.
+    pub fn set_sample_interval(&mut self, interval: u64) {
+        if interval == 0 {
+            panic!("TraceWriter - sample interval must be at least 1.");
+        }
+        self.sample_interval = interval;
+    }
+
+    /// Sets the maximum number of steps to record, with None meaning no limit.
+    pub fn set_max_records(&mut self, max_records: Option<u64>) {
+        self.max_records = max_records;
+    }
+
+    pub fn get_record_count(&self) -> u64 {
+        return self.record_count;
+    }
+
+    /// Checks if the maximum number of steps has been recorded.
+    pub fn is_full(&self) -> bool {
+        match self.max_records {
+            Some(max) => return self.record_count >= max,
+            None => return false,
+        }
+    }
+
+    /// Checks if the step with the given number (starting from 0) would be recorded.
+    pub fn is_recorded(&self, step_num: u64) -> bool {
+        return !self.is_full() && step_num.is_multiple_of(self.sample_interval);
+    }
+
+    /// Records the executed step with the given step number, if it is to be recorded.
+    pub fn record(&mut self, step_num: u64, step: &StepResult) -> io::Result<()> {
+        if !self.is_recorded(step_num) {
+            return Ok(());
+        }
+        match self.format {
+            TraceFormat::Csv => {
+                if self.record_count == 0 {
+                    self.write_csv_header(step.reg_before.len())?;
+                }
+                let registers = step.reg_before.iter().chain(step.reg_after.iter())
+                    .map(|x| x.to_string()).collect::<Vec<String>>();
+                writeln!(self.writer, "{},{},{},{}", step_num, step.ip, step.instruction, registers.join(","))?;
+            },
+            TraceFormat::JsonLines => {
+                writeln!(self.writer, "{{\"step\":{},\"ip\":{},\"instruction\":\"{}\",\"before\":{:?},\"after\":{:?}}}",
+                    step_num, step.ip, step.instruction, step.reg_before, step.reg_after)?;
+            }
+        }
+        self.record_count += 1;
+        return Ok(());
+    }
+
+    fn write_csv_header(&mut self, num_registers: usize) -> io::Result<()> {
+        let mut columns = vec![String::from("step"), String::from("ip"), String::from("instruction")];
+        columns.extend((0..num_registers).map(|x| format!("before_r{}", x)));
+        columns.extend((0..num_registers).map(|x| format!("after_r{}", x)));
+        return writeln!(self.writer, "{}", columns.join(","));
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        return self.writer.flush();
+    }
+
+    /// Consumes the tracer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+}
@@ -1,5 +1,25 @@
 use enum_iterator::IntoEnumIterator;
 
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use super::history::UndoEntry;
+use super::history::UndoLog;
+use super::optimise::MacroOp;
+use super::optimise::OptimisedInstruction;
+use super::optimise::OptimisedProgram;
+use super::optimise::optimise_program;
+use super::profile::Profile;
+use super::registers::RegisterFile;
+use super::snapshot::Snapshot;
+use super::trace::TraceWriter;
+
+/// Number of registers held by the wrist computer unless otherwise specified.
+pub const DEFAULT_REGISTER_COUNT: usize = 6;
+
 #[derive(Copy, Clone, IntoEnumIterator, Hash, PartialEq, Eq, Debug)]
 pub enum Operation {
     AddReg,     // add register
@@ -42,9 +62,59 @@ impl Operation {
             _ => None,
         }
     }
+
+    /// Gets the mnemonic for the operation, i.e. the inverse of Operation::from_string.
+    pub fn to_mnemonic(&self) -> &'static str {
+        match self {
+            Operation::AddReg => "addr",
+            Operation::AddImm => "addi",
+            Operation::MulReg => "mulr",
+            Operation::MulImm => "muli",
+            Operation::BitANDReg => "banr",
+            Operation::BitANDImm => "bani",
+            Operation::BitORReg => "borr",
+            Operation::BitORImm => "bori",
+            Operation::SetReg => "setr",
+            Operation::SetImm => "seti",
+            Operation::GtImmReg => "gtir",
+            Operation::GtRegImm => "gtri",
+            Operation::GtRegReg => "gtrr",
+            Operation::EqImmReg => "eqir",
+            Operation::EqRegImm => "eqri",
+            Operation::EqRegReg => "eqrr",
+        }
+    }
+
+    /// Checks if the operation is a comparison, which only produces 0 or 1.
+    pub fn is_comparison(&self) -> bool {
+        match self {
+            Operation::GtImmReg | Operation::GtRegImm | Operation::GtRegReg | Operation::EqImmReg |
+            Operation::EqRegImm | Operation::EqRegReg => return true,
+            _ => return false,
+        }
+    }
+
+    /// Checks if operand A of the operation refers to a register rather than an immediate value.
+    pub fn is_reg_a(&self) -> bool {
+        match self {
+            Operation::SetImm | Operation::GtImmReg | Operation::EqImmReg => return false,
+            _ => return true,
+        }
+    }
+
+    /// Checks if operand B of the operation refers to a register. Operand B is either an
+    /// immediate value or ignored for all other operations.
+    pub fn is_reg_b(&self) -> bool {
+        match self {
+            Operation::AddReg | Operation::MulReg | Operation::BitANDReg | Operation::BitORReg |
+            Operation::GtImmReg | Operation::GtRegReg | Operation::EqImmReg |
+            Operation::EqRegReg => return true,
+            _ => return false,
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Instruction {
     op: Operation,
     values: (usize, usize, usize)
@@ -65,156 +135,1229 @@ impl Instruction {
     pub fn get_values(&self) -> (usize, usize, usize) {
         return self.values;
     }
+
+    /// Gets the registers read by the instruction when it is executed.
+    pub fn get_read_registers(&self) -> Vec<usize> {
+        let mut output = Vec::<usize>::new();
+        if self.op.is_reg_a() {
+            output.push(self.values.0);
+        }
+        if self.op.is_reg_b() {
+            output.push(self.values.1);
+        }
+        return output;
+    }
+
+    /// Gets the register written by the instruction when it is executed.
+    pub fn get_write_register(&self) -> usize {
+        return self.values.2;
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, b, c) = self.values;
+        return write!(f, "{} {} {} {}", self.op.to_mnemonic(), a, b, c);
+    }
+}
+
+/// Record of a single instruction executed by the wrist computer.
+#[derive(Clone, Debug)]
+pub struct StepResult {
+    pub ip: usize,
+    pub instruction: Instruction,
+    pub reg_before: Vec<usize>,
+    pub reg_after: Vec<usize>,
+}
+
+/// Conditions on a register value that can be watched while running a program.
+/// 
+/// Value conditions only trigger on the step where they go from not holding to holding.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WatchCondition {
+    Read,               // register used as an operand
+    Written,            // register is the output of the instruction
+    Changed,            // register value is different after the instruction
+    Equals(usize),
+    GreaterThan(usize),
+    LessThan(usize),
+}
+
+/// A condition watched on a single register of the wrist computer.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Watchpoint {
+    pub reg: usize,
+    pub condition: WatchCondition,
+}
+
+impl Watchpoint {
+    pub fn new(reg: usize, condition: WatchCondition) -> Self {
+        Self {
+            reg: reg,
+            condition: condition,
+        }
+    }
+
+    /// Checks if the watchpoint is triggered by the given executed step. Conditions on the value
+    /// of a register are never triggered if the register is not part of the step.
+    pub fn is_triggered(&self, step: &StepResult) -> bool {
+        let (before, after) = match (step.reg_before.get(self.reg), step.reg_after.get(self.reg)) {
+            (Some(before), Some(after)) => (*before, *after),
+            _ => (0, 0),
+        };
+        match self.condition {
+            WatchCondition::Read => {
+                return step.instruction.get_read_registers().contains(&self.reg);
+            },
+            WatchCondition::Written => {
+                return step.instruction.get_write_register() == self.reg;
+            },
+            WatchCondition::Changed => {
+                return before != after;
+            },
+            WatchCondition::Equals(val) => {
+                return before != val && after == val;
+            },
+            WatchCondition::GreaterThan(val) => {
+                return before <= val && after > val;
+            },
+            WatchCondition::LessThan(val) => {
+                return before >= val && after < val;
+            }
+        }
+    }
+}
+
+/// Reasons for the wrist computer to stop running a program.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    Halted,                 // instruction pointer left the program
+    Breakpoint(usize),      // about to execute instruction at breakpoint address
+    Watchpoint(Watchpoint), // watched condition triggered by the last instruction executed
+}
+
+/// Errors raised when the wrist computer is unable to execute an instruction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecError {
+    InvalidRegister(usize),                         // operand refers to a register that doesn't exist
+    IpOutOfBounds(usize),                           // no instruction at ip when execution is entered
+    ArithmeticOverflow { value: u128, bits: u32 },  // checked result too large for the word width
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecError::InvalidRegister(reg) => return write!(f, "invalid register {}", reg),
+            ExecError::IpOutOfBounds(ip) => return write!(f, "instruction pointer {} out of bounds", ip),
+            ExecError::ArithmeticOverflow { value, bits } => {
+                return write!(f, "result {} overflows {}-bit word", value, bits);
+            }
+        }
+    }
+}
+
+impl Error for ExecError {}
+
+/// Behaviour when the result of an operation does not fit in the word width of the registers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OverflowMode {
+    Checked,    // treat overflow as an error
+    Wrapping,   // keep the lowest bits of the result
+    Saturating, // clamp the result to the largest value
+}
+
+/// Number of bits held by each register of the wrist computer, along with the overflow behaviour.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct WordWidth {
+    bits: u32,
+    overflow: OverflowMode,
+}
+
+impl WordWidth {
+    pub fn new(bits: u32, overflow: OverflowMode) -> Self {
+        if bits == 0 || bits > usize::BITS {
+            panic!("WristComputer - word width must be between 1 and {} bits.", usize::BITS);
+        }
+        Self {
+            bits: bits,
+            overflow: overflow,
+        }
+    }
+
+    /// Gets the full usize word width, with overflow treated as an error.
+    pub fn native() -> Self {
+        return WordWidth::new(usize::BITS, OverflowMode::Checked);
+    }
+
+    pub fn get_bits(&self) -> u32 {
+        return self.bits;
+    }
+
+    pub fn get_overflow_mode(&self) -> OverflowMode {
+        return self.overflow;
+    }
+
+    /// Gets the largest value that fits in the word width.
+    pub fn get_max(&self) -> usize {
+        return usize::MAX >> (usize::BITS - self.bits);
+    }
+
+    /// Fits the result of an operation into the word width. Panics if the result overflows and
+    /// overflow is checked.
+    pub fn fit(&self, value: u128) -> usize {
+        match self.try_fit(value) {
+            Ok(val) => return val,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Fits the result of an operation into the word width, returning an error if the result
+    /// overflows and overflow is checked.
+    pub fn try_fit(&self, value: u128) -> Result<usize, ExecError> {
+        let max = self.get_max();
+        if value <= max as u128 {
+            return Ok(value as usize);
+        }
+        match self.overflow {
+            OverflowMode::Checked => return Err(ExecError::ArithmeticOverflow { value: value, bits: self.bits }),
+            OverflowMode::Wrapping => return Ok((value & max as u128) as usize),
+            OverflowMode::Saturating => return Ok(max),
+        }
+    }
+}
+
+/// Outcome of running a program on the wrist computer with an instruction budget.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecOutcome {
+    Halted { steps: u64 },
+    BudgetExhausted { steps: u64, ip: usize, registers: Vec<usize> },
 }
 
 pub struct WristComputer {
-    registers: Vec<usize>,
+    registers: RegisterFile,
     ip_reg: Option<usize>,
-    ip_val: usize
+    ip_val: usize,
+    word: WordWidth,
+    steps: u64,
+    breakpoints: HashSet<usize>,
+    watchpoints: Vec<Watchpoint>,
+    profile: Option<Profile>,
+    history: Option<UndoLog>,
+    optimise: bool
 }
 
 impl WristComputer {
     pub fn new(ip_reg: Option<usize>) -> Self {
+        return WristComputer::with_registers(ip_reg, DEFAULT_REGISTER_COUNT);
+    }
+
+    /// Creates a new wrist computer with the given number of registers, all initialised to 0.
+    pub fn with_registers(ip_reg: Option<usize>, num_registers: usize) -> Self {
+        if let Some(reg) = ip_reg {
+            if reg >= num_registers {
+                panic!("WristComputer - instruction pointer register {} out of range.", reg);
+            }
+        }
         Self {
-            registers: vec![0; 6],
+            registers: RegisterFile::new(num_registers),
             ip_reg: ip_reg,
             ip_val: 0,
+            word: WordWidth::native(),
+            steps: 0,
+            breakpoints: HashSet::new(),
+            watchpoints: vec![],
+            profile: None,
+            history: None,
+            optimise: false
         }
     }
 
+    /// Creates a new wrist computer resuming from the state captured in the snapshot.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let mut wrist_comp = WristComputer::with_registers(snapshot.get_ip_reg(), snapshot.get_registers().len());
+        wrist_comp.restore(snapshot);
+        return wrist_comp;
+    }
+
+    /// Captures the registers, instruction pointer binding and value, and step counter of the wrist
+    /// computer. Breakpoints, watchpoints, profiles and history are not included.
+    pub fn snapshot(&self) -> Snapshot {
+        return Snapshot::new(self.registers.to_vec(), self.ip_reg, self.ip_val, self.steps);
+    }
+
+    /// Restores the state captured in the snapshot. Any recorded history is discarded, since it no
+    /// longer leads up to the current state.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.registers = RegisterFile::from_slice(snapshot.get_registers());
+        self.ip_reg = snapshot.get_ip_reg();
+        self.ip_val = snapshot.get_ip();
+        self.steps = snapshot.get_steps();
+        self.clear_history();
+    }
+
     pub fn update_register_zero(&mut self, val: usize) {
         self.registers[0] = val;
     }
 
     pub fn get_registers(&self) -> Vec<usize> {
-        return self.registers.clone();
+        return self.registers.to_vec();
     }
 
-    pub fn execute_program(&mut self, program: &Vec<Instruction>) {
-        // Re-initialise the instruction pointer to 0
-        self.ip_val = 0;
+    pub fn get_register(&self, reg: usize) -> usize {
+        return self.registers[reg];
+    }
+
+    /// Sets the value held in the given register, panicking if the register does not exist.
+    pub fn set_register(&mut self, reg: usize, val: usize) {
+        if reg >= self.registers.len() {
+            panic!("WristComputer - {}.", ExecError::InvalidRegister(reg));
+        }
+        self.registers[reg] = val;
+    }
+
+    pub fn get_register_count(&self) -> usize {
+        return self.registers.len();
+    }
 
-        if self.ip_reg.is_none() {
-            for instruct in program {
-                let after = WristComputer::perform_operation(&self.registers, &instruct);
-                self.registers = after;
+    pub fn get_word_width(&self) -> WordWidth {
+        return self.word;
+    }
+
+    /// Sets the word width that the results of all subsequent instructions are fitted to.
+    pub fn set_word_width(&mut self, word: WordWidth) {
+        self.word = word;
+    }
+
+    /// Gets the address of the next instruction to be executed.
+    pub fn get_ip(&self) -> usize {
+        return self.ip_val;
+    }
+
+    /// Moves the instruction pointer, so the next instruction executed is the one at the given
+    /// address. Any register bound to the instruction pointer is set to the address when that
+    /// instruction is executed.
+    pub fn set_ip(&mut self, ip: usize) {
+        self.ip_val = ip;
+    }
+
+    /// Gets the number of instructions executed since the program was started.
+    pub fn get_steps(&self) -> u64 {
+        return self.steps;
+    }
+
+    /// Adds a breakpoint on the given instruction address.
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Removes the breakpoint on the given instruction address, returning true if it was set.
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        return self.breakpoints.remove(&addr);
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Adds a watchpoint that stops execution when the condition on the given register triggers.
+    /// Returns an error (and adds nothing) if the register does not exist.
+    pub fn add_watchpoint(&mut self, reg: usize, condition: WatchCondition) -> Result<(), ExecError> {
+        if reg >= self.registers.len() {
+            return Err(ExecError::InvalidRegister(reg));
+        }
+        let watchpoint = Watchpoint::new(reg, condition);
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+        return Ok(());
+    }
+
+    /// Removes the matching watchpoint, returning true if it was set.
+    pub fn remove_watchpoint(&mut self, reg: usize, condition: WatchCondition) -> bool {
+        let watchpoint = Watchpoint::new(reg, condition);
+        let len_before = self.watchpoints.len();
+        self.watchpoints.retain(|x| *x != watchpoint);
+        return self.watchpoints.len() != len_before;
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Starts recording a profile of the instructions executed, discarding any existing profile.
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::new());
+    }
+
+    /// Stops profiling, returning the profile recorded since profiling was enabled.
+    pub fn disable_profiling(&mut self) -> Option<Profile> {
+        return self.profile.take();
+    }
+
+    pub fn get_profile(&self) -> Option<&Profile> {
+        return self.profile.as_ref();
+    }
+
+    /// Makes execute_program replace loops recognised by the optimiser with macro-ops, giving the
+    /// same registers in far fewer steps. Only applies while using the native word width.
+    pub fn enable_optimisation(&mut self) {
+        self.optimise = true;
+    }
+
+    pub fn disable_optimisation(&mut self) {
+        self.optimise = false;
+    }
+
+    /// Starts recording the changes made by each step so they can be undone, keeping the changes
+    /// for up to the given number of most recent steps (or all steps if no limit given).
+    pub fn enable_history(&mut self, limit: Option<usize>) {
+        self.history = Some(UndoLog::new(limit));
+    }
+
+    /// Stops recording changes, discarding all recorded history.
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Gets the number of steps that can currently be undone.
+    pub fn get_history_len(&self) -> usize {
+        match &self.history {
+            Some(history) => return history.len(),
+            None => return 0,
+        }
+    }
+
+    /// Undoes the most recent step recorded in the history, restoring the registers, instruction
+    /// pointer and step counter to exactly their previous state. Returns false if there is no
+    /// step to undo. Profiles are not affected.
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.history.as_mut().and_then(|x| x.pop()) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        match entry {
+            UndoEntry::Write { ip_val, ip_reg_old, dest, dest_old } => {
+                self.registers[dest] = dest_old;
+                if let Some((ip_reg, old_val)) = ip_reg_old {
+                    self.registers[ip_reg] = old_val;
+                }
+                self.ip_val = ip_val;
+            },
+            UndoEntry::Registers { ip_val, registers } => {
+                self.registers = registers;
+                self.ip_val = ip_val;
             }
-        } else {
-            loop {
-                // Check if instruction pointer still within bounds of program
-                if self.ip_val >= program.len() {
-                    break;
+        }
+        self.steps -= 1;
+        return true;
+    }
+
+    /// Steps back through the history until the predicate holds for the state of the wrist
+    /// computer, i.e. rewinds to the last point before the current state where it held. Returns
+    /// false if the history runs out before the predicate holds.
+    pub fn run_back_until<F>(&mut self, predicate: F) -> bool where F: Fn(&WristComputer) -> bool {
+        while self.step_back() {
+            if predicate(self) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Executes the program from the start until it halts. If optimisation is enabled, loops
+    /// recognised by the optimiser are run as macro-ops.
+    pub fn execute_program(&mut self, program: &[Instruction]) {
+        if self.optimise && self.word == WordWidth::native() {
+            let optimised = optimise_program(program, self.ip_reg);
+            self.execute_optimised_program(&optimised);
+            return;
+        }
+        self.execute_program_with_budget(program, None);
+    }
+
+    /// Executes the program from the start, stopping early if the maximum number of instructions
+    /// given by the budget has been executed before the program halts.
+    pub fn execute_program_with_budget(&mut self, program: &[Instruction], budget: Option<u64>)
+            -> ExecOutcome {
+        match self.try_execute_program(program, budget) {
+            Ok(outcome) => return outcome,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Executes the program from the start in the same way as execute_program_with_budget, but
+    /// returns an error instead of panicking if an instruction cannot be executed.
+    /// 
+    /// An empty program halts immediately without executing anything.
+    pub fn try_execute_program(&mut self, program: &[Instruction], budget: Option<u64>)
+            -> Result<ExecOutcome, ExecError> {
+        self.restart();
+        if program.is_empty() {
+            return Ok(ExecOutcome::Halted { steps: 0 });
+        }
+        loop {
+            if let Some(max_steps) = budget {
+                if self.steps >= max_steps && self.ip_val < program.len() {
+                    return Ok(ExecOutcome::BudgetExhausted {
+                        steps: self.steps,
+                        ip: self.ip_val,
+                        registers: self.registers.to_vec()
+                    });
                 }
-                // Write instruction pointer value to register
-                self.registers[self.ip_reg.unwrap()] = self.ip_val;
-                // Get next instruction to execute
-                let instruction = program[self.ip_val];
-                
-                // println!("[{}] {:?} --- {:?}", self.ip_val, instruction.get_operation(), instruction.get_values());
-                // println!(">>>> Reg state: {:?}", self.registers);
-                // println!("");
+            }
+            if !self.try_execute_next(program)? {
+                return Ok(ExecOutcome::Halted { steps: self.steps });
+            }
+        }
+    }
 
-                // Execute instruction
-                let after = WristComputer::perform_operation(&self.registers, &instruction);
-                self.registers = after;
-                // Retrieve value from IP register and increment
-                self.ip_val = self.registers[self.ip_reg.unwrap()] + 1;
+    /// Executes the program from the start until it halts, recording each step to the tracer.
+    pub fn execute_program_traced<W: Write>(&mut self, program: &[Instruction], tracer: &mut TraceWriter<W>)
+            -> io::Result<()> {
+        self.restart();
+        loop {
+            // Only gather step details if they are going to be recorded
+            let step_num = self.steps;
+            if tracer.is_recorded(step_num) {
+                match self.step(program) {
+                    Some(step) => tracer.record(step_num, &step)?,
+                    None => break,
+                }
+            } else if !self.execute_next(program) {
+                break;
             }
         }
+        return tracer.flush();
     }
 
-    pub fn perform_operation(before: &Vec<usize>, instruction: &Instruction) -> Vec<usize> {
-        let op = instruction.get_operation();
-        let values = instruction.get_values();
-        let mut after = before.clone();
-        match op {
-            Operation::AddReg => {
-                let res = before[values.0] + before[values.1];
-                after[values.2] = res;
-            },
-            Operation::AddImm => {
-                let res = before[values.0] + values.1;
-                after[values.2] = res;
-            },
-            Operation::MulReg => {
-                let res = before[values.0] * before[values.1];
-                after[values.2] = res;
-            },
-            Operation::MulImm => {
-                let res = before[values.0] * values.1;
-                after[values.2] = res;
-            },
-            Operation::BitANDReg => {
-                let res = before[values.0] & before[values.1];
-                after[values.2] = res;
-            },
-            Operation::BitANDImm => {
-                let res = before[values.0] & values.1;
-                after[values.2] = res;
-            },
-            Operation::BitORReg => {
-                let res = before[values.0] | before[values.1];
-                after[values.2] = res;
-            },
-            Operation::BitORImm => {
-                let res = before[values.0] | values.1;
-                after[values.2] = res;
-            },
-            Operation::SetReg => {
-                after[values.2] = before[values.0];
-            },
-            Operation::SetImm => {
-                after[values.2] = values.0;
-            },
-            Operation::GtImmReg => {
-                let comp = values.0 > before[values.1];
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+    /// Executes the optimised program from the start until it halts, leaving the registers in the
+    /// same state as executing the original program.
+    /// 
+    /// Macro-ops do not account for restricted word widths, so the wrist computer must be using the
+    /// native word width.
+    pub fn execute_optimised_program(&mut self, program: &OptimisedProgram) {
+        if program.get_ip_reg() != self.ip_reg {
+            panic!("WristComputer - optimised program bound to different instruction pointer register.");
+        }
+        if self.word.get_bits() != usize::BITS && program.get_macro_count() > 0 {
+            panic!("WristComputer - macro-ops cannot be executed with restricted word width.");
+        }
+        self.restart();
+        let instructions = program.get_instructions();
+        while self.ip_val < instructions.len() {
+            match &instructions[self.ip_val] {
+                OptimisedInstruction::Native(instruction) => self.execute_instruction(instruction),
+                OptimisedInstruction::Macro(macro_op, replaced) => self.execute_macro(macro_op, replaced),
+            }
+        }
+    }
+
+    /// Executes the single instruction at the current instruction pointer.
+    /// 
+    /// Returns None if the instruction pointer is outside of the program (i.e. it has halted).
+    pub fn step(&mut self, program: &[Instruction]) -> Option<StepResult> {
+        match self.try_step(program) {
+            Ok(step) => return Some(step),
+            Err(ExecError::IpOutOfBounds(_)) => return None,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Executes the single instruction at the current instruction pointer, returning an error if
+    /// the instruction cannot be executed or the program has already halted. The wrist computer is
+    /// left unchanged if an error is returned.
+    pub fn try_step(&mut self, program: &[Instruction]) -> Result<StepResult, ExecError> {
+        if self.ip_val >= program.len() {
+            return Err(ExecError::IpOutOfBounds(self.ip_val));
+        }
+        let ip = self.ip_val;
+        let instruction = program[ip];
+        let mut reg_before = self.registers.to_vec();
+        if let Some(ip_reg) = self.ip_reg {
+            reg_before[ip_reg] = ip;
+        }
+        self.try_execute_next(program)?;
+        return Ok(StepResult {
+            ip: ip,
+            instruction: instruction,
+            reg_before: reg_before,
+            reg_after: self.registers.to_vec(),
+        });
+    }
+
+    /// Resumes execution of the program until it halts, reaches an instruction address with a
+    /// breakpoint set or an instruction triggers a watchpoint.
+    /// 
+    /// At least one instruction is executed, so calling this again after stopping at a breakpoint
+    /// continues past it.
+    pub fn run_until_breakpoint(&mut self, program: &[Instruction]) -> StopReason {
+        match self.try_run_until_breakpoint(program) {
+            Ok(stop) => return stop,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Fallible version of run_until_breakpoint, returning an error if an instruction cannot be
+    /// executed. The wrist computer is left at the instruction that failed.
+    pub fn try_run_until_breakpoint(&mut self, program: &[Instruction]) -> Result<StopReason, ExecError> {
+        loop {
+            if self.watchpoints.is_empty() {
+                if !self.try_execute_next(program)? {
+                    return Ok(StopReason::Halted);
                 }
-            },
-            Operation::GtRegImm => {
-                let comp = before[values.0] > values.1;
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+            } else {
+                // Only record the step details when they are needed to check watchpoints
+                let step = match self.try_step(program) {
+                    Ok(step) => step,
+                    Err(ExecError::IpOutOfBounds(_)) => return Ok(StopReason::Halted),
+                    Err(err) => return Err(err),
+                };
+                for watchpoint in self.watchpoints.iter() {
+                    if watchpoint.is_triggered(&step) {
+                        return Ok(StopReason::Watchpoint(*watchpoint));
+                    }
                 }
-            },
-            Operation::GtRegReg => {
-                let comp = before[values.0] > before[values.1];
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+            }
+            if self.ip_val < program.len() && self.breakpoints.contains(&self.ip_val) {
+                return Ok(StopReason::Breakpoint(self.ip_val));
+            }
+        }
+    }
+
+    /// Re-initialises the instruction pointer and step counter to 0 before running a program from
+    /// the start. Recorded history is discarded, as it belongs to the previous run.
+    fn restart(&mut self) {
+        self.ip_val = 0;
+        self.steps = 0;
+        self.clear_history();
+    }
+
+    /// Discards all recorded history, keeping the same limit on the steps recorded.
+    fn clear_history(&mut self) {
+        if let Some(history) = self.history.as_ref() {
+            self.history = Some(UndoLog::new(history.get_limit()));
+        }
+    }
+
+    /// Executes the instruction at the current instruction pointer and advances the instruction
+    /// pointer. Returns false without executing anything if the program has already halted.
+    fn execute_next(&mut self, program: &[Instruction]) -> bool {
+        match self.try_execute_next(program) {
+            Ok(executed) => return executed,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Fallible version of execute_next.
+    fn try_execute_next(&mut self, program: &[Instruction]) -> Result<bool, ExecError> {
+        // Check if instruction pointer still within bounds of program
+        if self.ip_val >= program.len() {
+            return Ok(false);
+        }
+        let instruction = program[self.ip_val];
+        self.try_execute_instruction(&instruction)?;
+        return Ok(true);
+    }
+
+    /// Executes the given instruction as the one located at the current instruction pointer.
+    fn execute_instruction(&mut self, instruction: &Instruction) {
+        if let Err(err) = self.try_execute_instruction(instruction) {
+            panic!("WristComputer - {}.", err);
+        }
+    }
+
+    /// Fallible version of execute_instruction, leaving the wrist computer unchanged on error.
+    fn try_execute_instruction(&mut self, instruction: &Instruction) -> Result<(), ExecError> {
+        // Write instruction pointer value to register, keeping old value in case of error
+        let mut ip_reg_old: Option<(usize, usize)> = None;
+        if let Some(ip_reg) = self.ip_reg {
+            ip_reg_old = Some((ip_reg, self.registers[ip_reg]));
+            self.registers[ip_reg] = self.ip_val;
+        }
+        // Execute instruction
+        let ip_before = self.ip_val;
+        let dest = instruction.get_write_register();
+        let dest_old = self.registers.as_slice().get(dest).copied();
+        let word = self.word;
+        match WristComputer::try_apply_operation(self.registers.as_mut_slice(), instruction, word) {
+            Ok(()) => (),
+            Err(err) => {
+                if let Some((ip_reg, old_val)) = ip_reg_old {
+                    self.registers[ip_reg] = old_val;
                 }
+                return Err(err);
+            }
+        }
+        if let (Some(history), Some(dest_old)) = (self.history.as_mut(), dest_old) {
+            history.push(UndoEntry::Write {
+                ip_val: ip_before,
+                ip_reg_old: ip_reg_old,
+                dest: dest,
+                dest_old: dest_old
+            });
+        }
+        // Retrieve value from IP register and increment
+        match self.ip_reg {
+            Some(ip_reg) => self.ip_val = self.registers[ip_reg].saturating_add(1),
+            None => self.ip_val += 1,
+        }
+        self.record_step(ip_before);
+        return Ok(());
+    }
+
+    /// Executes the given macro-op as the one located at the current instruction pointer. Counts as
+    /// a single step regardless of how many instructions it replaces.
+    ///
+    /// If the macro-op cannot be applied without overflowing, the instruction it replaced is
+    /// executed instead, so the loop is run one instruction at a time.
+    fn execute_macro(&mut self, macro_op: &MacroOp, replaced: &Instruction) {
+        let ip_reg = self.ip_reg.expect("WristComputer - macro-op executed without instruction pointer register.");
+        let ip_before = self.ip_val;
+        let mut registers = self.registers;
+        registers[ip_reg] = self.ip_val;
+        let ip_after = match macro_op.apply(registers.as_mut_slice(), ip_reg) {
+            Some(ip_after) => ip_after,
+            None => {
+                self.execute_instruction(replaced);
+                return;
             },
-            Operation::EqImmReg => {
-                let comp = values.0 == before[values.1];
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+        };
+        if let Some(history) = self.history.as_mut() {
+            history.push(UndoEntry::Registers { ip_val: ip_before, registers: self.registers });
+        }
+        self.registers = registers;
+        self.ip_val = ip_after;
+        self.record_step(ip_before);
+    }
+
+    /// Updates the step counter and profile after executing the instruction at the given address.
+    fn record_step(&mut self, ip_before: usize) {
+        self.steps += 1;
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record(ip_before, self.ip_val);
+        }
+    }
+
+    pub fn perform_operation(before: &[usize], instruction: &Instruction) -> Vec<usize> {
+        return WristComputer::perform_operation_with_width(before, instruction, WordWidth::native());
+    }
+
+    /// Performs the instruction on the given registers, with the result fitted to the word width.
+    pub fn perform_operation_with_width(before: &[usize], instruction: &Instruction, word: WordWidth) -> Vec<usize> {
+        match WristComputer::try_perform_operation_with_width(before, instruction, word) {
+            Ok(after) => return after,
+            Err(err) => panic!("WristComputer - {}.", err),
+        }
+    }
+
+    /// Fallible version of perform_operation, returning an error instead of panicking if the
+    /// instruction refers to a register that doesn't exist or the result overflows.
+    pub fn try_perform_operation(before: &[usize], instruction: &Instruction) -> Result<Vec<usize>, ExecError> {
+        return WristComputer::try_perform_operation_with_width(before, instruction, WordWidth::native());
+    }
+
+    /// Fallible version of perform_operation_with_width.
+    pub fn try_perform_operation_with_width(before: &[usize], instruction: &Instruction, word: WordWidth)
+            -> Result<Vec<usize>, ExecError> {
+        let mut after = before.to_vec();
+        WristComputer::try_apply_operation(&mut after, instruction, word)?;
+        return Ok(after);
+    }
+
+    /// Performs the instruction by updating the registers in place. The registers are left
+    /// unchanged if an error is returned.
+    pub fn try_apply_operation(registers: &mut [usize], instruction: &Instruction, word: WordWidth)
+            -> Result<(), ExecError> {
+        let dest = instruction.get_write_register();
+        if dest >= registers.len() {
+            return Err(ExecError::InvalidRegister(dest));
+        }
+        let res = WristComputer::calculate_result(registers, instruction)?;
+        registers[dest] = word.try_fit(res)?;
+        return Ok(());
+    }
+
+    /// Calculates the result of the instruction without limiting it to any word width.
+    fn calculate_result(before: &[usize], instruction: &Instruction) -> Result<u128, ExecError> {
+        let (a, b, _c) = instruction.get_values();
+        let op = instruction.get_operation();
+        if op.is_reg_a() && a >= before.len() {
+            return Err(ExecError::InvalidRegister(a));
+        }
+        if op.is_reg_b() && b >= before.len() {
+            return Err(ExecError::InvalidRegister(b));
+        }
+        let reg = |i: usize| before[i] as u128;
+        let res = match op {
+            Operation::AddReg => reg(a) + reg(b),
+            Operation::AddImm => reg(a) + b as u128,
+            Operation::MulReg => reg(a) * reg(b),
+            Operation::MulImm => reg(a) * b as u128,
+            Operation::BitANDReg => reg(a) & reg(b),
+            Operation::BitANDImm => reg(a) & b as u128,
+            Operation::BitORReg => reg(a) | reg(b),
+            Operation::BitORImm => reg(a) | b as u128,
+            Operation::SetReg => reg(a),
+            Operation::SetImm => a as u128,
+            Operation::GtImmReg => (a as u128 > reg(b)) as u128,
+            Operation::GtRegImm => (reg(a) > b as u128) as u128,
+            Operation::GtRegReg => (reg(a) > reg(b)) as u128,
+            Operation::EqImmReg => (a as u128 == reg(b)) as u128,
+            Operation::EqRegImm => (reg(a) == b as u128) as u128,
+            Operation::EqRegReg => (reg(a) == reg(b)) as u128,
+        };
+        return Ok(res);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::program::Program;
+    use super::super::trace::TraceFormat;
+    use super::super::super::rng::XorShiftRng;
+
+    /// Example program given in AoC 2018 Day 19 (bound to "#ip 0").
+    fn example_program() -> Vec<Instruction> {
+        return vec![
+            Instruction::new(Operation::SetImm, (5, 0, 1)),
+            Instruction::new(Operation::SetImm, (6, 0, 2)),
+            Instruction::new(Operation::AddImm, (0, 1, 0)),
+            Instruction::new(Operation::AddReg, (1, 2, 3)),
+            Instruction::new(Operation::SetReg, (1, 0, 0)),
+            Instruction::new(Operation::SetImm, (8, 0, 4)),
+            Instruction::new(Operation::SetImm, (9, 0, 5)),
+        ];
+    }
+
+    #[test]
+    fn test_wristcomp_step() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        let mut visited = Vec::<usize>::new();
+        while let Some(step) = wrist_comp.step(&program) {
+            visited.push(step.ip);
+            assert_eq!(step.ip, step.reg_before[0]);
+        }
+        assert_eq!(vec![0, 1, 2, 4, 6], visited);
+        assert_eq!(vec![6, 5, 6, 0, 0, 9], wrist_comp.get_registers());
+        assert!(wrist_comp.step(&program).is_none());
+    }
+
+    #[test]
+    fn test_wristcomp_run_until_breakpoint() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        wrist_comp.add_breakpoint(4);
+        wrist_comp.add_breakpoint(6);
+        assert_eq!(StopReason::Breakpoint(4), wrist_comp.run_until_breakpoint(&program));
+        assert_eq!(vec![3, 5, 6, 0, 0, 0], wrist_comp.get_registers());
+        assert_eq!(StopReason::Breakpoint(6), wrist_comp.run_until_breakpoint(&program));
+        assert!(wrist_comp.remove_breakpoint(6));
+        assert_eq!(StopReason::Halted, wrist_comp.run_until_breakpoint(&program));
+        assert_eq!(vec![6, 5, 6, 0, 0, 9], wrist_comp.get_registers());
+    }
+
+    #[test]
+    fn test_wristcomp_watchpoints() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        wrist_comp.add_watchpoint(1, WatchCondition::Read).unwrap();
+        wrist_comp.add_watchpoint(2, WatchCondition::GreaterThan(5)).unwrap();
+        assert_eq!(Err(ExecError::InvalidRegister(6)), wrist_comp.add_watchpoint(6, WatchCondition::Changed));
+        let stop = wrist_comp.run_until_breakpoint(&program);
+        assert_eq!(StopReason::Watchpoint(Watchpoint::new(2, WatchCondition::GreaterThan(5))), stop);
+        assert_eq!(2, wrist_comp.get_ip());
+        // Register 1 is next read by the "setr 1 0 0" instruction at address 4
+        let stop = wrist_comp.run_until_breakpoint(&program);
+        assert_eq!(StopReason::Watchpoint(Watchpoint::new(1, WatchCondition::Read)), stop);
+        assert_eq!(vec![5, 5, 6, 0, 0, 0], wrist_comp.get_registers());
+        assert!(wrist_comp.remove_watchpoint(1, WatchCondition::Read));
+        wrist_comp.add_watchpoint(5, WatchCondition::Written).unwrap();
+        let stop = wrist_comp.run_until_breakpoint(&program);
+        assert_eq!(StopReason::Watchpoint(Watchpoint::new(5, WatchCondition::Written)), stop);
+        assert_eq!(StopReason::Halted, wrist_comp.run_until_breakpoint(&program));
+        // Watchpoints on registers missing from a step are never triggered
+        let step = StepResult {
+            ip: 0,
+            instruction: Instruction::new(Operation::SetImm, (7, 0, 1)),
+            reg_before: vec![0, 0],
+            reg_after: vec![0, 7],
+        };
+        assert!(!Watchpoint::new(6, WatchCondition::Changed).is_triggered(&step));
+        assert!(!Watchpoint::new(6, WatchCondition::Equals(0)).is_triggered(&step));
+        assert!(Watchpoint::new(1, WatchCondition::Changed).is_triggered(&step));
+    }
+
+    #[test]
+    fn test_wristcomp_execute_with_budget() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        let outcome = wrist_comp.execute_program_with_budget(&program, Some(3));
+        let expected = ExecOutcome::BudgetExhausted { steps: 3, ip: 4, registers: vec![3, 5, 6, 0, 0, 0] };
+        assert_eq!(expected, outcome);
+        let outcome = wrist_comp.execute_program_with_budget(&program, Some(5));
+        assert_eq!(ExecOutcome::Halted { steps: 5 }, outcome);
+        // Program never leaves the instruction at address 1
+        let program = vec![
+            Instruction::new(Operation::SetImm, (0, 0, 1)),
+            Instruction::new(Operation::SetImm, (0, 0, 0)),
+        ];
+        let outcome = wrist_comp.execute_program_with_budget(&program, Some(1000));
+        let expected = ExecOutcome::BudgetExhausted { steps: 1000, ip: 1, registers: vec![0, 0, 6, 0, 0, 9] };
+        assert_eq!(expected, outcome);
+    }
+
+    #[test]
+    fn test_wristcomp_word_width() {
+        let program = vec![
+            Instruction::new(Operation::SetImm, (16777215, 0, 0)),
+            Instruction::new(Operation::AddImm, (0, 2, 1)),
+            Instruction::new(Operation::MulImm, (0, 65899, 2)),
+        ];
+        let mut wrist_comp = WristComputer::with_registers(None, 3);
+        wrist_comp.set_word_width(WordWidth::new(24, OverflowMode::Wrapping));
+        wrist_comp.execute_program(&program);
+        assert_eq!(vec![16777215, 1, 16711317], wrist_comp.get_registers());
+        let mut wrist_comp = WristComputer::with_registers(None, 3);
+        wrist_comp.set_word_width(WordWidth::new(24, OverflowMode::Saturating));
+        wrist_comp.execute_program(&program);
+        assert_eq!(vec![16777215, 16777215, 16777215], wrist_comp.get_registers());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wristcomp_word_width_checked_overflow() {
+        let program = vec![
+            Instruction::new(Operation::SetImm, (usize::MAX, 0, 0)),
+            Instruction::new(Operation::AddImm, (0, 1, 0)),
+        ];
+        let mut wrist_comp = WristComputer::new(None);
+        wrist_comp.execute_program(&program);
+    }
+
+    #[test]
+    fn test_wristcomp_exec_errors() {
+        let before = vec![1, 2, 3, 4];
+        let instruction = Instruction::new(Operation::AddReg, (1, 4, 0));
+        assert_eq!(Err(ExecError::InvalidRegister(4)), WristComputer::try_perform_operation(&before, &instruction));
+        let instruction = Instruction::new(Operation::SetImm, (7, 0, 5));
+        assert_eq!(Err(ExecError::InvalidRegister(5)), WristComputer::try_perform_operation(&before, &instruction));
+        let word = WordWidth::new(8, OverflowMode::Checked);
+        let instruction = Instruction::new(Operation::MulImm, (3, 100, 0));
+        let expected = Err(ExecError::ArithmeticOverflow { value: 400, bits: 8 });
+        assert_eq!(expected, WristComputer::try_perform_operation_with_width(&before, &instruction, word));
+        // Computer left unchanged by failed step
+        let program = vec![Instruction::new(Operation::SetImm, (3, 0, 1)), Instruction::new(Operation::AddReg, (0, 9, 0))];
+        let mut wrist_comp = WristComputer::new(Some(2));
+        assert!(wrist_comp.try_step(&program).is_ok());
+        assert_eq!(Err(ExecError::InvalidRegister(9)), wrist_comp.try_step(&program).map(|x| x.ip));
+        assert_eq!(1, wrist_comp.get_ip());
+        assert_eq!(vec![0, 3, 0, 0, 0, 0], wrist_comp.get_registers());
+        assert_eq!(Err(ExecError::InvalidRegister(9)), wrist_comp.try_run_until_breakpoint(&program));
+        assert_eq!(1, wrist_comp.get_ip());
+    }
+
+    #[test]
+    fn test_wristcomp_empty_program() {
+        let mut wrist_comp = WristComputer::new(Some(2));
+        wrist_comp.update_register_zero(7);
+        assert_eq!(Ok(ExecOutcome::Halted { steps: 0 }), wrist_comp.try_execute_program(&[], None));
+        wrist_comp.execute_program(&[]);
+        assert_eq!(0, wrist_comp.get_steps());
+        assert_eq!(vec![7, 0, 0, 0, 0, 0], wrist_comp.get_registers());
+    }
+
+    #[test]
+    fn test_wristcomp_trace() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        let mut tracer = TraceWriter::new(Vec::<u8>::new(), TraceFormat::Csv);
+        wrist_comp.execute_program_traced(&program, &mut tracer).unwrap();
+        let output = String::from_utf8(tracer.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(6, lines.len());
+        assert!(lines[0].starts_with("step,ip,instruction,before_r0,"));
+        assert_eq!("2,2,addi 0 1 0,2,5,6,0,0,0,3,5,6,0,0,0", lines[3]);
+        // Only every other step recorded, up to the limit
+        let mut wrist_comp = WristComputer::new(Some(0));
+        let mut tracer = TraceWriter::new(Vec::<u8>::new(), TraceFormat::JsonLines);
+        tracer.set_sample_interval(2);
+        tracer.set_max_records(Some(2));
+        wrist_comp.execute_program_traced(&program, &mut tracer).unwrap();
+        assert_eq!(vec![6, 5, 6, 0, 0, 9], wrist_comp.get_registers());
+        let output = String::from_utf8(tracer.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(2, lines.len());
+        let expected = "{\"step\":2,\"ip\":2,\"instruction\":\"addi 0 1 0\",\"before\":[2, 5, 6, 0, 0, 0],\"after\":[3, 5, 6, 0, 0, 0]}";
+        assert_eq!(expected, lines[1]);
+    }
+
+    #[test]
+    fn test_wristcomp_step_back() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let mut wrist_comp = WristComputer::new(program.get_ip_reg());
+        wrist_comp.enable_history(None);
+        let mut states = Vec::<(usize, Vec<usize>)>::new();
+        for _ in 0..500 {
+            states.push((wrist_comp.get_ip(), wrist_comp.get_registers()));
+            wrist_comp.step(program.get_instructions());
+        }
+        // Undo every step, checking the state matches exactly
+        while let Some((ip, registers)) = states.pop() {
+            assert!(wrist_comp.step_back());
+            assert_eq!(ip, wrist_comp.get_ip());
+            assert_eq!(registers, wrist_comp.get_registers());
+            assert_eq!(states.len() as u64, wrist_comp.get_steps());
+        }
+        assert!(!wrist_comp.step_back());
+    }
+
+    #[test]
+    fn test_wristcomp_step_back_after_rerun() {
+        let program = example_program();
+        let mut wrist_comp = WristComputer::new(Some(0));
+        wrist_comp.enable_history(None);
+        wrist_comp.execute_program(&program);
+        let registers = wrist_comp.get_registers();
+        wrist_comp.execute_program(&program);
+        assert_eq!(5, wrist_comp.get_history_len());
+        // History only reaches back to the start of the second run
+        for _ in 0..5 {
+            assert!(wrist_comp.step_back());
+        }
+        assert!(!wrist_comp.step_back());
+        assert_eq!(0, wrist_comp.get_steps());
+        assert_eq!(0, wrist_comp.get_ip());
+        assert_eq!(registers[1..], wrist_comp.get_registers()[1..]);
+    }
+
+    #[test]
+    fn test_wristcomp_run_back_until() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let mut wrist_comp = WristComputer::new(program.get_ip_reg());
+        wrist_comp.enable_history(None);
+        wrist_comp.execute_program_with_budget(program.get_instructions(), Some(20000));
+        // Find when r0 last changed - the "addr 3 0 0" instruction at address 7
+        let r0 = wrist_comp.get_register(0);
+        assert!(wrist_comp.run_back_until(|x| x.get_register(0) != r0));
+        assert_eq!(7, wrist_comp.get_ip());
+        // History is limited to the most recent steps
+        wrist_comp.enable_history(Some(100));
+        wrist_comp.execute_program_with_budget(program.get_instructions(), Some(10000));
+        assert_eq!(100, wrist_comp.get_history_len());
+        assert!(!wrist_comp.run_back_until(|x| x.get_steps() == 0));
+        assert_eq!(9900, wrist_comp.get_steps());
+    }
+
+    #[test]
+    fn test_wristcomp_snapshot() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let mut wrist_comp = WristComputer::new(program.get_ip_reg());
+        wrist_comp.execute_program_with_budget(program.get_instructions(), Some(1000));
+        let text = wrist_comp.snapshot().to_string();
+        for _ in 0..1000 {
+            wrist_comp.step(program.get_instructions());
+        }
+        // Resuming from the snapshot reaches the same state as running straight through
+        let mut resumed = WristComputer::from_snapshot(&text.parse::<Snapshot>().unwrap());
+        for _ in 0..1000 {
+            resumed.step(program.get_instructions());
+        }
+        assert_eq!(wrist_comp.snapshot(), resumed.snapshot());
+        // Fork with a modified register
+        let mut snapshot = text.parse::<Snapshot>().unwrap();
+        snapshot.set_register(0, 1);
+        let forked = WristComputer::from_snapshot(&snapshot);
+        assert_eq!(1, forked.get_register(0));
+        assert_eq!(1000, forked.get_steps());
+    }
+
+    #[test]
+    fn test_wristcomp_profiling_d19() {
+        let program = std::fs::read_to_string("./input/2018/day19.txt").unwrap().parse::<Program>().unwrap();
+        let mut wrist_comp = WristComputer::new(program.get_ip_reg());
+        wrist_comp.enable_profiling();
+        wrist_comp.execute_program(program.get_instructions());
+        let profile = wrist_comp.disable_profiling().unwrap();
+        assert_eq!(wrist_comp.get_steps(), profile.get_total_steps());
+        // Inner loop incrementing r1 dominates execution
+        let mut hottest = profile.get_hottest_addresses(8).iter().map(|x| x.0).collect::<Vec<usize>>();
+        hottest.sort();
+        assert_eq!(vec![3, 4, 5, 6, 8, 9, 10, 11], hottest);
+        let back_edges = profile.get_back_edges();
+        assert_eq!((11, 3), back_edges[0].0);
+        assert_eq!((15, 2), back_edges[1].0);
+        assert_eq!(1, profile.get_transition_count(16, 257));
+    }
+
+    /// Number of random cases checked for each operation against the reference model.
+    const CASES: usize = 2000;
+
+    const NUM_REGISTERS: usize = 4;
+
+    /// Reference model of a single operation as described in AoC 2018 Day 16, returning None if the
+    /// result overflows a usize.
+    fn reference_result(op: Operation, regs: &[usize], a: usize, b: usize) -> Option<usize> {
+        return match op {
+            Operation::AddReg => regs[a].checked_add(regs[b]),
+            Operation::AddImm => regs[a].checked_add(b),
+            Operation::MulReg => regs[a].checked_mul(regs[b]),
+            Operation::MulImm => regs[a].checked_mul(b),
+            Operation::BitANDReg => Some(regs[a] & regs[b]),
+            Operation::BitANDImm => Some(regs[a] & b),
+            Operation::BitORReg => Some(regs[a] | regs[b]),
+            Operation::BitORImm => Some(regs[a] | b),
+            Operation::SetReg => Some(regs[a]),
+            Operation::SetImm => Some(a),
+            Operation::GtImmReg => Some(if a > regs[b] { 1 } else { 0 }),
+            Operation::GtRegImm => Some(if regs[a] > b { 1 } else { 0 }),
+            Operation::GtRegReg => Some(if regs[a] > regs[b] { 1 } else { 0 }),
+            Operation::EqImmReg => Some(if a == regs[b] { 1 } else { 0 }),
+            Operation::EqRegImm => Some(if regs[a] == b { 1 } else { 0 }),
+            Operation::EqRegReg => Some(if regs[a] == regs[b] { 1 } else { 0 }),
+        };
+    }
+
+    /// Reference model of a single operation on registers holding the given number of bits, with
+    /// results wrapping around.
+    fn reference_result_wrapping(op: Operation, regs: &[usize], a: usize, b: usize, bits: u32) -> usize {
+        let mask = usize::MAX >> (usize::BITS - bits);
+        let res = match op {
+            Operation::AddReg => regs[a].wrapping_add(regs[b]),
+            Operation::AddImm => regs[a].wrapping_add(b),
+            Operation::MulReg => regs[a].wrapping_mul(regs[b]),
+            Operation::MulImm => regs[a].wrapping_mul(b),
+            _ => reference_result(op, regs, a, b).unwrap(),
+        };
+        return res & mask;
+    }
+
+    /// Generates a value that is small half of the time, so that comparisons are sometimes equal and
+    /// arithmetic sometimes avoids overflow.
+    fn random_value(rng: &mut XorShiftRng, max: usize) -> usize {
+        if rng.next_below(2) == 0 {
+            return rng.next_in_range(0, 16.min(max));
+        }
+        return rng.next_in_range(0, max);
+    }
+
+    /// Generates random registers and operands valid for the operation.
+    fn random_case(rng: &mut XorShiftRng, op: Operation, max: usize) -> (Vec<usize>, Instruction) {
+        let regs = (0..NUM_REGISTERS).map(|_| random_value(rng, max)).collect::<Vec<usize>>();
+        let a = if op.is_reg_a() { rng.next_in_range(0, NUM_REGISTERS - 1) } else { random_value(rng, max) };
+        let b = if op.is_reg_b() { rng.next_in_range(0, NUM_REGISTERS - 1) } else { random_value(rng, max) };
+        let c = rng.next_in_range(0, NUM_REGISTERS - 1);
+        return (regs, Instruction::new(op, (a, b, c)));
+    }
+
+    #[test]
+    fn test_ops_match_reference() {
+        let mut rng = XorShiftRng::new(16);
+        for op in Operation::into_enum_iter() {
+            for _ in 0..CASES {
+                let (regs, instruction) = random_case(&mut rng, op, usize::MAX);
+                let (a, b, c) = instruction.get_values();
+                let result = WristComputer::try_perform_operation(&regs, &instruction);
+                match reference_result(op, &regs, a, b) {
+                    Some(expected) => {
+                        let mut after = regs.clone();
+                        after[c] = expected;
+                        assert_eq!(Ok(after), result, "{} on {:?}", instruction, regs);
+                    },
+                    None => {
+                        let overflowed = matches!(result, Err(ExecError::ArithmeticOverflow { .. }));
+                        assert!(overflowed, "{} on {:?} should overflow", instruction, regs);
+                    }
                 }
-            },
-            Operation::EqRegImm => {
-                let comp = before[values.0] == values.1;
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+            }
+        }
+    }
+
+    #[test]
+    fn test_ops_match_reference_wrapping() {
+        let mut rng = XorShiftRng::new(19);
+        for bits in [8, 16, 36] {
+            let word = WordWidth::new(bits, OverflowMode::Wrapping);
+            for op in Operation::into_enum_iter() {
+                for _ in 0..CASES {
+                    let (regs, instruction) = random_case(&mut rng, op, word.get_max());
+                    let (a, b, c) = instruction.get_values();
+                    let after = WristComputer::perform_operation_with_width(&regs, &instruction, word);
+                    assert_eq!(reference_result_wrapping(op, &regs, a, b, bits), after[c], "{} on {:?}", instruction, regs);
                 }
-            },
-            Operation::EqRegReg => {
-                let comp = before[values.0] == before[values.1];
-                if comp {
-                    after[values.2] = 1;
-                } else {
-                    after[values.2] = 0;
+            }
+        }
+    }
+
+    #[test]
+    fn test_ops_only_write_register_c() {
+        let mut rng = XorShiftRng::new(21);
+        for op in Operation::into_enum_iter() {
+            for _ in 0..CASES {
+                let (regs, instruction) = random_case(&mut rng, op, 1000);
+                let after = WristComputer::perform_operation(&regs, &instruction);
+                let c = instruction.get_write_register();
+                for reg in (0..NUM_REGISTERS).filter(|reg| *reg != c) {
+                    assert_eq!(regs[reg], after[reg], "{} on {:?}", instruction, regs);
                 }
             }
         }
-        return after;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ops_algebraic_properties() {
+        let mut rng = XorShiftRng::new(2018);
+        for _ in 0..CASES {
+            let regs = (0..NUM_REGISTERS).map(|_| random_value(&mut rng, usize::MAX)).collect::<Vec<usize>>();
+            let a = rng.next_in_range(0, NUM_REGISTERS - 1);
+            let b = rng.next_in_range(0, NUM_REGISTERS - 1);
+            let c = rng.next_in_range(0, NUM_REGISTERS - 1);
+            let ignored = random_value(&mut rng, usize::MAX);
+            let perform = |op: Operation, values: (usize, usize, usize)| {
+                return WristComputer::try_perform_operation(&regs, &Instruction::new(op, values)).map(|x| x[values.2]);
+            };
+            // setr and seti copy regardless of operand b
+            assert_eq!(Ok(regs[a]), perform(Operation::SetReg, (a, ignored, c)));
+            assert_eq!(Ok(ignored), perform(Operation::SetImm, (ignored, ignored, c)));
+            // Comparisons only produce 0 or 1
+            for op in Operation::into_enum_iter().filter(|op| op.is_comparison()) {
+                let (a_val, b_val) = (if op.is_reg_a() { a } else { ignored }, if op.is_reg_b() { b } else { ignored });
+                let res = perform(op, (a_val, b_val, c)).unwrap();
+                assert!(res == 0 || res == 1, "{:?} gave {}", op, res);
+            }
+            assert_eq!(Ok(1), perform(Operation::EqRegReg, (a, a, c)));
+            assert_eq!(Ok(0), perform(Operation::GtRegReg, (a, a, c)));
+            // Register/register arithmetic and bitwise operations are commutative
+            for op in [Operation::AddReg, Operation::MulReg, Operation::BitANDReg, Operation::BitORReg] {
+                assert_eq!(perform(op, (a, b, c)), perform(op, (b, a, c)), "{:?}", op);
+            }
+            // Identities
+            assert_eq!(Ok(regs[a]), perform(Operation::AddImm, (a, 0, c)));
+            assert_eq!(Ok(regs[a]), perform(Operation::MulImm, (a, 1, c)));
+            assert_eq!(Ok(0), perform(Operation::MulImm, (a, 0, c)));
+            assert_eq!(Ok(regs[a]), perform(Operation::BitANDReg, (a, a, c)));
+            assert_eq!(Ok(regs[a]), perform(Operation::BitORReg, (a, a, c)));
+            assert_eq!(Ok(regs[a]), perform(Operation::BitORImm, (a, 0, c)));
+            assert_eq!(Ok(0), perform(Operation::BitANDImm, (a, 0, c)));
+        }
+    }
+
+    #[test]
+    fn test_ops_invalid_registers() {
+        let regs = vec![1, 2, 3, 4];
+        for op in Operation::into_enum_iter() {
+            let instruction = Instruction::new(op, (0, 0, NUM_REGISTERS));
+            let result = WristComputer::try_perform_operation(&regs, &instruction);
+            assert_eq!(Err(ExecError::InvalidRegister(NUM_REGISTERS)), result, "{:?}", op);
+            if op.is_reg_a() {
+                let result = WristComputer::try_perform_operation(&regs, &Instruction::new(op, (NUM_REGISTERS, 0, 0)));
+                assert_eq!(Err(ExecError::InvalidRegister(NUM_REGISTERS)), result, "{:?}", op);
+            }
+            if op.is_reg_b() {
+                let result = WristComputer::try_perform_operation(&regs, &Instruction::new(op, (0, NUM_REGISTERS, 0)));
+                assert_eq!(Err(ExecError::InvalidRegister(NUM_REGISTERS)), result, "{:?}", op);
+            }
+        }
+    }
+}